@@ -0,0 +1,19 @@
+use std::time::Instant;
+
+/**
+ * Returns the minimum elapsed time (in nanoseconds) over runs runs of f,
+ *  along with the result of the last one.
+ */
+pub fn measure_elapsed_time<T, F: FnMut() -> T>(runs: usize, mut f: F) -> (u128, T) {
+    assert!(runs > 0, "At least one run is needed.");
+
+    let mut min_elapsed = u128::MAX;
+    let mut result = None;
+    for _ in 0..runs {
+        let start = Instant::now();
+        result = Some(f());
+        min_elapsed = min_elapsed.min(start.elapsed().as_nanos());
+    }
+
+    (min_elapsed, result.unwrap())
+}
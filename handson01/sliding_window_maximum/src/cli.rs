@@ -0,0 +1,476 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+
+use sliding_window_maximum::{brute_force, brute_force_idiomatic, bst, heap, indexed_heap, linear};
+
+pub const USAGE: &str = "\
+Usage:
+    sliding_window_maximum [bench]
+        Benchmark every strategy on random and ascending vectors and write results.csv.
+
+    sliding_window_maximum run -k <K> [options] [FILE]
+        Print the maximum of every window of size K, one per line.
+        Numbers are read from FILE, or from stdin if FILE is missing or '-'.
+
+Options:
+    -k <K>                  Window size (required)
+    -s, --strategy <NAME>   brute_force, brute_force_idiomatic, heap, indexed_heap, bst
+                            or linear (default: linear)
+    -i, --indices           Prefix each maximum with the 0-based index of its window's first element
+    --csv <COLUMN>          Read the numbers from a CSV column, given as a 0-based index or a header name
+    --column-index <INDEX>  Same as --csv, but always taking the column as a 0-based index
+    --column-name <NAME>    Same as --csv, but always taking the column as a header name,
+                            for headers that are numbers themselves
+    --headers               The first CSV row is a header (implied when the column is a name)
+    --delimiter <CHAR>      CSV field delimiter (default: ',')
+                            --headers and --delimiter require one of the column options
+    -h, --help              Print this message";
+
+pub type Strategy = fn(&Vec<i32>, i32) -> Vec<i32>;
+
+// Column of a CSV file, identified either by position or by header name.
+enum Column {
+    Index(usize),
+    Name(String),
+}
+
+// Layout of the input numbers.
+enum Format {
+    // Numbers separated by any mix of spaces and newlines
+    Plain,
+    Csv {
+        column: Column,
+        headers: bool,
+        delimiter: u8,
+    },
+}
+
+struct Options {
+    k: i32,
+    strategy: Strategy,
+    indices: bool,
+    format: Format,
+    path: Option<String>,
+}
+
+/**
+ * Returns the strategy called name, if any.
+ */
+pub fn strategy_by_name(name: &str) -> Option<Strategy> {
+    match name {
+        "brute_force" => Some(brute_force),
+        "brute_force_idiomatic" => Some(brute_force_idiomatic),
+        "heap" => Some(heap),
+        "indexed_heap" => Some(indexed_heap),
+        "bst" => Some(bst),
+        "linear" => Some(linear),
+        _ => None,
+    }
+}
+
+/**
+ * Runs the command line mode with the given arguments
+ *  (program name and "run" subcommand excluded).
+ */
+pub fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let options = parse_args(args)?;
+
+    let input: Box<dyn Read> = match options.path.as_deref() {
+        None | Some("-") => Box::new(io::stdin().lock()),
+        Some(path) => {
+            Box::new(File::open(path).map_err(|why| format!("Cannot open {}: {}", path, why))?)
+        }
+    };
+
+    let nums = match &options.format {
+        Format::Plain => read_plain(input)?,
+        Format::Csv {
+            column,
+            headers,
+            delimiter,
+        } => read_csv(input, column, *headers, *delimiter)?,
+    };
+
+    if options.k as usize > nums.len() {
+        return Err(format!(
+            "k ({}) is larger than the number of elements ({}).",
+            options.k,
+            nums.len()
+        )
+        .into());
+    }
+
+    let maximums = (options.strategy)(&nums, options.k);
+
+    let mut out = BufWriter::new(io::stdout().lock());
+    for (i, max) in maximums.iter().enumerate() {
+        if options.indices {
+            writeln!(out, "{} {}", i, max)?;
+        } else {
+            writeln!(out, "{}", max)?;
+        }
+    }
+    out.flush()?;
+
+    Ok(())
+}
+
+fn parse_args(args: &[String]) -> Result<Options, Box<dyn Error>> {
+    let mut k: Option<i32> = None;
+    let mut strategy: Strategy = linear;
+    let mut indices = false;
+    let mut column: Option<Column> = None;
+    let mut headers = false;
+    let mut delimiter: Option<u8> = None;
+    let mut path: Option<String> = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-k" => {
+                let val = next_value(&mut args, arg)?;
+                k = match val.parse() {
+                    Ok(k) if k >= 1 => Some(k),
+                    _ => return Err(format!("k must be a positive integer, got '{}'.", val).into()),
+                };
+            }
+            "-s" | "--strategy" => {
+                let name = next_value(&mut args, arg)?;
+                strategy = strategy_by_name(name)
+                    .ok_or_else(|| format!("Unknown strategy '{}'.", name))?;
+            }
+            "-i" | "--indices" => indices = true,
+            "--csv" | "--column-index" | "--column-name" => {
+                if column.is_some() {
+                    return Err(
+                        "Only one of --csv, --column-index and --column-name can be given.".into(),
+                    );
+                }
+
+                let val = next_value(&mut args, arg)?;
+                column = Some(match (arg.as_str(), val.parse()) {
+                    ("--column-name", _) => Column::Name(val.to_string()),
+                    (_, Ok(index)) => Column::Index(index),
+                    ("--column-index", Err(_)) => {
+                        return Err(format!(
+                            "Column index must be a non-negative integer, got '{}'.",
+                            val
+                        )
+                        .into())
+                    }
+                    (_, Err(_)) => Column::Name(val.to_string()),
+                });
+            }
+            "--headers" => headers = true,
+            "--delimiter" => {
+                let val = next_value(&mut args, arg)?;
+                delimiter = match val.as_bytes() {
+                    [byte] => Some(*byte),
+                    _ => {
+                        return Err(
+                            format!("Delimiter must be a single character, got '{}'.", val).into(),
+                        )
+                    }
+                };
+            }
+            _ if arg.starts_with('-') && arg != "-" => {
+                return Err(format!("Unknown option '{}'.", arg).into())
+            }
+            _ => {
+                if path.is_some() {
+                    return Err("Only one input file can be given.".into());
+                }
+                path = Some(arg.clone());
+            }
+        }
+    }
+
+    let format = match column {
+        // CSV options are most likely a forgotten column, rather than meant to be ignored
+        None if headers => return Err("--headers requires a CSV column (see --csv).".into()),
+        None if delimiter.is_some() => {
+            return Err("--delimiter requires a CSV column (see --csv).".into())
+        }
+        None => Format::Plain,
+        Some(column) => Format::Csv {
+            // A column name only makes sense if there's a header row
+            headers: headers || matches!(column, Column::Name(_)),
+            column,
+            delimiter: delimiter.unwrap_or(b','),
+        },
+    };
+
+    Ok(Options {
+        k: k.ok_or("Missing window size (-k).")?,
+        strategy,
+        indices,
+        format,
+        path,
+    })
+}
+
+fn next_value<'a>(
+    args: &mut impl Iterator<Item = &'a String>,
+    option: &str,
+) -> Result<&'a str, Box<dyn Error>> {
+    match args.next() {
+        Some(val) => Ok(val),
+        None => Err(format!("Missing value for option '{}'.", option).into()),
+    }
+}
+
+/**
+ * Reads integers separated by whitespace and/or newlines.
+ */
+fn read_plain(mut input: impl Read) -> Result<Vec<i32>, Box<dyn Error>> {
+    let mut buffer = String::new();
+    input.read_to_string(&mut buffer)?;
+
+    let mut nums = Vec::new();
+    for (line_no, line) in buffer.lines().enumerate() {
+        for token in line.split_whitespace() {
+            let num = token
+                .parse()
+                .map_err(|_| format!("line {}: '{}' is not an integer.", line_no + 1, token))?;
+            nums.push(num);
+        }
+    }
+
+    Ok(nums)
+}
+
+/**
+ * Reads the integers stored in one column of a CSV file.
+ */
+fn read_csv(
+    input: impl Read,
+    column: &Column,
+    headers: bool,
+    delimiter: u8,
+) -> Result<Vec<i32>, Box<dyn Error>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(headers)
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_reader(input);
+
+    let index = match column {
+        Column::Index(index) => *index,
+        Column::Name(name) => reader
+            .headers()?
+            .iter()
+            .position(|header| header.trim() == name)
+            .ok_or_else(|| format!("No column named '{}'.", name))?,
+    };
+
+    let mut nums = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        let line = record.position().map_or(0, |pos| pos.line());
+        let field = record
+            .get(index)
+            .ok_or_else(|| format!("line {}: missing column {}.", line, index))?;
+        let num = field
+            .trim()
+            .parse()
+            .map_err(|_| format!("line {}: '{}' is not an integer.", line, field))?;
+        nums.push(num);
+    }
+
+    Ok(nums)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    // Message of the error returned by parse_args
+    fn parse_error(line: &[&str]) -> String {
+        match parse_args(&args(line)) {
+            Ok(_) => panic!("{:?} should not parse", line),
+            Err(why) => why.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let options = parse_args(&args(&["-k", "3", "--csv", "price", "prices.csv"])).unwrap();
+        assert_eq!(options.k, 3);
+        assert_eq!(options.path.as_deref(), Some("prices.csv"));
+        match options.format {
+            Format::Csv {
+                column: Column::Name(name),
+                headers,
+                delimiter,
+            } => {
+                assert_eq!(name, "price");
+                // Implied by the column name
+                assert!(headers);
+                assert_eq!(delimiter, b',');
+            }
+            _ => panic!("expected a CSV column name"),
+        }
+
+        assert_eq!(
+            parse_error(&["-k", "3", "--verbose"]),
+            "Unknown option '--verbose'."
+        );
+        assert_eq!(parse_error(&["-s", "linear"]), "Missing window size (-k).");
+        assert_eq!(parse_error(&["-k"]), "Missing value for option '-k'.");
+        assert_eq!(
+            parse_error(&["-k", "0"]),
+            "k must be a positive integer, got '0'."
+        );
+        let options = parse_args(&args(&["-k", "2", "-s", "indexed_heap"])).unwrap();
+        assert_eq!(
+            (options.strategy)(&vec![1, 3, 2, 0], options.k),
+            vec![3, 3, 2]
+        );
+        assert_eq!(
+            parse_error(&["-k", "2", "-s", "quick"]),
+            "Unknown strategy 'quick'."
+        );
+    }
+
+    #[test]
+    fn test_parse_csv_args() {
+        // --csv guesses, the other two options don't
+        let columns = [
+            (&["--csv", "2"], Some(2), None),
+            (&["--csv", "2020"], Some(2020), None),
+            (&["--column-index", "2020"], Some(2020), None),
+            (&["--column-name", "2020"], None, Some("2020")),
+            (&["--column-name", "price"], None, Some("price")),
+        ];
+        for (column_args, expected_index, expected_name) in columns {
+            let options = parse_args(&args(&[&["-k", "1"][..], column_args].concat())).unwrap();
+            match options.format {
+                Format::Csv {
+                    column: Column::Index(index),
+                    headers,
+                    ..
+                } => {
+                    assert_eq!(Some(index), expected_index);
+                    assert!(!headers);
+                }
+                Format::Csv {
+                    column: Column::Name(name),
+                    headers,
+                    ..
+                } => {
+                    assert_eq!(Some(name.as_str()), expected_name);
+                    assert!(headers);
+                }
+                Format::Plain => panic!("{:?} should select a CSV column", column_args),
+            }
+        }
+
+        let options = parse_args(&args(&[
+            "-k",
+            "1",
+            "--column-index",
+            "0",
+            "--headers",
+            "--delimiter",
+            ";",
+        ]))
+        .unwrap();
+        assert!(matches!(
+            options.format,
+            Format::Csv {
+                column: Column::Index(0),
+                headers: true,
+                delimiter: b';',
+            }
+        ));
+
+        assert_eq!(
+            parse_error(&["-k", "1", "--column-index", "price"]),
+            "Column index must be a non-negative integer, got 'price'."
+        );
+        assert_eq!(
+            parse_error(&["-k", "1", "--csv", "0", "--column-name", "price"]),
+            "Only one of --csv, --column-index and --column-name can be given."
+        );
+        assert_eq!(
+            parse_error(&["-k", "1", "--headers"]),
+            "--headers requires a CSV column (see --csv)."
+        );
+        assert_eq!(
+            parse_error(&["-k", "1", "--delimiter", ";", "input.txt"]),
+            "--delimiter requires a CSV column (see --csv)."
+        );
+    }
+
+    #[test]
+    fn test_read_plain() {
+        let nums = read_plain("3 -1\n\n4  1\t5\n".as_bytes()).unwrap();
+        assert_eq!(nums, vec![3, -1, 4, 1, 5]);
+
+        let why = read_plain("3 1\n4 x 5\n".as_bytes()).unwrap_err();
+        assert_eq!(why.to_string(), "line 2: 'x' is not an integer.");
+    }
+
+    #[test]
+    fn test_read_csv() {
+        let csv = "day,price\n1,10\n2,-3\n3,7\n";
+
+        // By index, skipping the header row
+        let nums = read_csv(csv.as_bytes(), &Column::Index(1), true, b',').unwrap();
+        assert_eq!(nums, vec![10, -3, 7]);
+
+        // By header name, with spaces around it
+        let nums = read_csv(
+            "day , price \n1,10\n2,-3\n".as_bytes(),
+            &Column::Name(String::from("price")),
+            true,
+            b',',
+        )
+        .unwrap();
+        assert_eq!(nums, vec![10, -3]);
+
+        // Custom delimiter, no header row
+        let nums = read_csv("1;10\n2;-3\n".as_bytes(), &Column::Index(0), false, b';').unwrap();
+        assert_eq!(nums, vec![1, 2]);
+
+        // Errors report the line of the offending record
+        let why = read_csv(
+            "day,price\n1,10\n2,ten\n".as_bytes(),
+            &Column::Index(1),
+            true,
+            b',',
+        )
+        .unwrap_err();
+        assert_eq!(why.to_string(), "line 3: 'ten' is not an integer.");
+
+        let why = read_csv(
+            csv.as_bytes(),
+            &Column::Name(String::from("volume")),
+            true,
+            b',',
+        )
+        .unwrap_err();
+        assert_eq!(why.to_string(), "No column named 'volume'.");
+    }
+
+    #[test]
+    fn test_k_larger_than_input() {
+        let path = env::temp_dir().join(format!("swm_cli_test_{}.txt", std::process::id()));
+        fs::write(&path, "1 2 3\n").unwrap();
+
+        let result = run(&args(&["-k", "4", path.to_str().unwrap()]));
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "k (4) is larger than the number of elements (3)."
+        );
+    }
+}
@@ -80,6 +80,159 @@ pub fn heap(nums: &Vec<i32>, k: i32) -> Vec<i32> {
     max_vals
 }
 
+pub fn indexed_heap(nums: &Vec<i32>, k: i32) -> Vec<i32> {
+    let k = k as usize;
+
+    if k < 1 || nums.len() < k {
+        panic!("k must be a positive number no larger than nums' length.")
+    }
+
+    let mut max_vals: Vec<i32> = Vec::with_capacity(nums.len() - k + 1);
+    let mut heap = IndexedHeap::with_capacity(k);
+
+    for (i, num) in nums.iter().enumerate() {
+        // Element i - k and element i share the same handle, so the outgoing
+        // element must be removed before inserting the new one.
+        // This keeps the heap's size bounded by k.
+        if i >= k {
+            heap.remove(i % k);
+        }
+
+        heap.push(i % k, *num);
+
+        // The root is always inside the window, as stale elements
+        // are removed as soon as they leave it.
+        if i >= k - 1 {
+            max_vals.push(heap.peek().unwrap());
+        }
+    }
+
+    max_vals
+}
+
+// Binary max-heap whose elements can be accessed through a handle.
+// Handles are integers in the 0..capacity range, chosen by the caller.
+pub struct IndexedHeap {
+    heap: Vec<Candidate>,          // Heap-ordered values, tagged with their handle
+    positions: Vec<Option<usize>>, // Position in heap of each handle, if present
+}
+
+impl IndexedHeap {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            heap: Vec::with_capacity(capacity),
+            positions: vec![None; capacity],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    // Returns the maximum value, if any.
+    pub fn peek(&self) -> Option<i32> {
+        self.heap.first().map(|candidate| candidate.val)
+    }
+
+    // Inserts val with the given handle, which must not already be in use.
+    pub fn push(&mut self, handle: usize, val: i32) {
+        if self.positions[handle].is_some() {
+            panic!("Handle {} is already in use.", handle);
+        }
+
+        self.heap.push(Candidate { val, pos: handle });
+        self.positions[handle] = Some(self.heap.len() - 1);
+        self.sift_up(self.heap.len() - 1);
+    }
+
+    // Removes the maximum value and returns it along with its handle.
+    pub fn pop(&mut self) -> Option<(usize, i32)> {
+        let root = self.heap.first()?;
+        let (handle, val) = (root.pos, root.val);
+        self.remove(handle);
+
+        Some((handle, val))
+    }
+
+    // Returns the value associated to handle, if any.
+    pub fn get(&self, handle: usize) -> Option<i32> {
+        self.positions[handle].map(|i| self.heap[i].val)
+    }
+
+    // Replaces the value associated to handle, restoring the heap property.
+    pub fn update(&mut self, handle: usize, val: i32) {
+        let i = self.positions[handle].expect("Handle not in use.");
+        let old_val = self.heap[i].val;
+        self.heap[i].val = val;
+
+        if val > old_val {
+            self.sift_up(i);
+        } else {
+            self.sift_down(i);
+        }
+    }
+
+    // Removes the value associated to handle and returns it, if any.
+    pub fn remove(&mut self, handle: usize) -> Option<i32> {
+        let i = self.positions[handle]?;
+        let last = self.heap.len() - 1;
+
+        // Move the element to the bottom, where it can be safely removed,
+        // then fix the position of the element that took its place.
+        self.swap(i, last);
+        let removed = self.heap.pop().unwrap();
+        self.positions[handle] = None;
+
+        if i < last {
+            self.sift_up(i);
+            self.sift_down(i);
+        }
+
+        Some(removed.val)
+    }
+
+    fn sift_up(&mut self, mut i: usize) {
+        while i > 0 {
+            let parent = (i - 1) / 2;
+            if self.heap[i].val <= self.heap[parent].val {
+                break;
+            }
+
+            self.swap(i, parent);
+            i = parent;
+        }
+    }
+
+    fn sift_down(&mut self, mut i: usize) {
+        loop {
+            let mut largest = i;
+            for child in [2 * i + 1, 2 * i + 2] {
+                if child < self.heap.len() && self.heap[child].val > self.heap[largest].val {
+                    largest = child;
+                }
+            }
+
+            if largest == i {
+                break;
+            }
+
+            self.swap(i, largest);
+            i = largest;
+        }
+    }
+
+    // Swaps two heap elements, keeping the position map consistent.
+    fn swap(&mut self, i: usize, j: usize) {
+        self.heap.swap(i, j);
+        self.positions[self.heap[i].pos] = Some(i);
+        self.positions[self.heap[j].pos] = Some(j);
+    }
+}
+
 pub fn bst(nums: &Vec<i32>, k: i32) -> Vec<i32> {
     let k = k as usize;
 
@@ -188,6 +341,48 @@ mod tests {
         assert_eq!(results, truth);
     }
 
+    #[test]
+    fn test_indexed_heap_version() {
+        let k = 3;
+        let v = gen_random_vector(100);
+
+        let results = indexed_heap(&v, k);
+        let truth = brute_force(&v, k);
+
+        assert_eq!(results, truth);
+    }
+
+    #[test]
+    fn test_indexed_heap_ascending() {
+        let k = 5;
+        let v: Vec<i32> = (0..100).collect();
+
+        let results = indexed_heap(&v, k);
+        let truth = brute_force(&v, k);
+
+        assert_eq!(results, truth);
+    }
+
+    #[test]
+    fn test_indexed_heap_operations() {
+        let mut heap = IndexedHeap::with_capacity(4);
+        heap.push(0, 5);
+        heap.push(1, 9);
+        heap.push(2, 1);
+        heap.push(3, 7);
+        assert_eq!(heap.peek(), Some(9));
+
+        heap.remove(1);
+        assert_eq!(heap.peek(), Some(7));
+        assert_eq!(heap.get(1), None);
+
+        heap.update(2, 8);
+        assert_eq!(heap.pop(), Some((2, 8)));
+        assert_eq!(heap.pop(), Some((3, 7)));
+        assert_eq!(heap.pop(), Some((0, 5)));
+        assert!(heap.is_empty());
+    }
+
     #[test]
     fn test_bst_version() {
         let k = 3;
@@ -1,11 +1,45 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::env;
 use std::fs;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::Instant;
 
 use sliding_window_maximum::{
-    brute_force, brute_force_idiomatic, bst, gen_random_vector, heap, linear,
+    brute_force, brute_force_idiomatic, bst, gen_random_vector, heap, indexed_heap, linear,
 };
 
+mod cli;
+
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", cli::USAGE);
+        return;
+    }
+
+    match args.first().map(String::as_str) {
+        None | Some("bench") => bench(),
+        Some("run") => {
+            if let Err(why) = cli::run(&args[1..]) {
+                eprintln!("Error: {}", why);
+                eprintln!("Run with --help for usage.");
+                process::exit(1);
+            }
+        }
+        Some(other) => {
+            eprintln!("Unknown command '{}'.\n\n{}", other, cli::USAGE);
+            process::exit(1);
+        }
+    }
+}
+
+/**
+ * Measures the execution time and peak memory of every strategy on random
+ *  and ascending vectors and writes the results to results.csv.
+ */
+fn bench() {
     let ns = [
         1024,
         2 * 1024,
@@ -16,46 +50,47 @@ fn main() {
         64 * 1024,
         128 * 1024,
     ];
-    let ks = [1];
+    // With k = 1 every window is trivial, the larger ones show how
+    // the heap-based strategies scale with the window size
+    let ks = [1, 32, 1024];
+
+    let strategies: [(&str, cli::Strategy); 6] = [
+        ("BruteForce", brute_force),
+        ("BruteForceIdiomatic", brute_force_idiomatic),
+        ("Heap", heap),
+        ("IndexedHeap", indexed_heap),
+        ("BST", bst),
+        ("Linear", linear),
+    ];
 
     // Write csv header
-    let mut output_text: String = "Method,n,k,elapsed\n".to_string();
+    let mut output_text: String = "Method,input,n,k,elapsed,peak_bytes\n".to_string();
 
     for &n in ns.iter() {
-        for &k in ks.iter() {
-            if k as usize > n {
-                continue;
+        // On ascending input the plain heap never gets to discard anything,
+        // so it grows to n elements, while the indexed heap stays bounded by k
+        let inputs = [
+            ("random", gen_random_vector(n)),
+            ("ascending", (0..n as i32).collect::<Vec<i32>>()),
+        ];
+
+        for (input, nums) in inputs.iter() {
+            for &k in ks.iter() {
+                if k as usize > n {
+                    continue;
+                }
+
+                for &(name, strategy) in strategies.iter() {
+                    let (elapsed_times, _) = measure_elapsed_time(strategy, nums, k);
+                    let min_elapsed = *elapsed_times.iter().min().unwrap();
+                    let peak_bytes = measure_peak_memory(strategy, nums, k);
+                    let row = format!(
+                        "{},{},{},{},{},{}\n",
+                        name, input, n, k, min_elapsed, peak_bytes
+                    );
+                    output_text.push_str(&row);
+                }
             }
-            let nums = gen_random_vector(n);
-            // Brute force
-            let (elapsed_times, _) = measure_elapsed_time(brute_force, &nums, k);
-            let min_elapsed = *elapsed_times.iter().min().unwrap();
-            let row = format!("{},{},{},{}\n", "BruteForce", n, k, min_elapsed);
-            output_text.push_str(&row);
-
-            // Brute force idiomatic
-            let (elapsed_times, _) = measure_elapsed_time(brute_force_idiomatic, &nums, k);
-            let min_elapsed = *elapsed_times.iter().min().unwrap();
-            let row = format!("{},{},{},{}\n", "BruteForceIdiomatic", n, k, min_elapsed);
-            output_text.push_str(&row);
-
-            // Heap
-            let (elapsed_times, _) = measure_elapsed_time(heap, &nums, k);
-            let min_elapsed = *elapsed_times.iter().min().unwrap();
-            let row = format!("{},{},{},{}\n", "Heap", n, k, min_elapsed);
-            output_text.push_str(&row);
-
-            // Bst
-            let (elapsed_times, _) = measure_elapsed_time(bst, &nums, k);
-            let min_elapsed = *elapsed_times.iter().min().unwrap();
-            let row = format!("{},{},{},{}\n", "BST", n, k, min_elapsed);
-            output_text.push_str(&row);
-
-            // Linear
-            let (elapsed_times, _) = measure_elapsed_time(linear, &nums, k);
-            let min_elapsed = *elapsed_times.iter().min().unwrap();
-            let row = format!("{},{},{},{}\n", "Linear", n, k, min_elapsed);
-            output_text.push_str(&row);
         }
     }
 
@@ -81,3 +116,48 @@ fn measure_elapsed_time(
 
     (elapsed_times, results)
 }
+
+// Wraps the system allocator to keep track of the heap memory in use,
+// so that we can compare the memory footprint of the different methods.
+struct TrackingAllocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK_ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for TrackingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK_ALLOCATED.fetch_max(allocated, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: TrackingAllocator = TrackingAllocator;
+
+/**
+ * Returns the peak number of bytes allocated by f on top of
+ *  the memory already in use when it's called.
+ */
+fn measure_peak_memory(
+    f: fn(vec: &Vec<i32>, k: i32) -> Vec<i32>,
+    nums: &Vec<i32>,
+    k: i32,
+) -> usize {
+    let baseline = ALLOCATED.load(Ordering::Relaxed);
+    PEAK_ALLOCATED.store(baseline, Ordering::Relaxed);
+
+    let results = f(nums, k);
+    let peak = PEAK_ALLOCATED.load(Ordering::Relaxed);
+    drop(results);
+
+    peak - baseline
+}
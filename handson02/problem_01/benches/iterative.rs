@@ -0,0 +1,121 @@
+use std::fs;
+use std::path::Path;
+
+use bench_timer::measure_elapsed_time;
+use problem_01::iterative::IterativeSegmentTree;
+use problem_01::SegmentTree;
+
+const N_RUNS: usize = 5;
+
+enum Query {
+    Update { left: usize, right: usize, val: i32 },
+    Max { left: usize, right: usize },
+}
+
+/**
+ * Compares the recursive and the iterative segment trees on the
+ *  tests/input*.txt workloads and prints the results as csv.
+ */
+fn main() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests");
+
+    println!("Input,n,m,Recursive,Iterative");
+    for i in 0.. {
+        let path = dir.join(format!("input{}.txt", i));
+        let Ok(text) = fs::read_to_string(&path) else {
+            break;
+        };
+        let (elems, queries) = parse_input(&text);
+
+        let (recursive_elapsed, recursive_results) = measure_elapsed_time(N_RUNS, || {
+            let mut seg_tree = SegmentTree::new(&elems);
+            run_queries(&queries, |left, right, val| match val {
+                Some(val) => {
+                    seg_tree.chmin(left..=right, val).unwrap();
+                    None
+                }
+                None => Some(seg_tree.max(left..=right).unwrap()),
+            })
+        });
+
+        let (iterative_elapsed, iterative_results) = measure_elapsed_time(N_RUNS, || {
+            let mut seg_tree = IterativeSegmentTree::new(&elems);
+            run_queries(&queries, |left, right, val| match val {
+                Some(val) => {
+                    seg_tree.chmin(left..=right, val).unwrap();
+                    None
+                }
+                None => Some(seg_tree.max(left..=right).unwrap()),
+            })
+        });
+
+        assert_eq!(
+            recursive_results, iterative_results,
+            "Mismatch on input{}",
+            i
+        );
+
+        println!(
+            "input{},{},{},{},{}",
+            i,
+            elems.len(),
+            queries.len(),
+            recursive_elapsed,
+            iterative_elapsed
+        );
+    }
+}
+
+/**
+ * Runs every query through f, which receives the query's bounds and,
+ *  for updates, its value. Returns the answers to the max queries.
+ */
+fn run_queries<F: FnMut(usize, usize, Option<i32>) -> Option<i32>>(
+    queries: &[Query],
+    mut f: F,
+) -> Vec<i32> {
+    let mut results = Vec::new();
+    for query in queries {
+        match *query {
+            Query::Update { left, right, val } => {
+                f(left, right, Some(val));
+            }
+            Query::Max { left, right } => results.extend(f(left, right, None)),
+        }
+    }
+
+    results
+}
+
+fn parse_input(text: &str) -> (Vec<i32>, Vec<Query>) {
+    let mut lines = text.lines();
+    let mut next_numbers = || -> Vec<i32> {
+        lines
+            .next()
+            .expect("Unexpected end of input.")
+            .split_whitespace()
+            .map(|x| x.parse().expect("Not an integer!"))
+            .collect()
+    };
+
+    let m = next_numbers()[1] as usize;
+    let elems = next_numbers();
+
+    let queries = (0..m)
+        .map(|_| {
+            let query = next_numbers();
+            let left = (query[1] - 1) as usize;
+            let right = (query[2] - 1) as usize;
+            match query[0] {
+                0 => Query::Update {
+                    left,
+                    right,
+                    val: query[3],
+                },
+                _ => Query::Max { left, right },
+            }
+        })
+        .collect();
+
+    (elems, queries)
+}
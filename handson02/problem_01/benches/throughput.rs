@@ -0,0 +1,73 @@
+use std::env;
+use std::fmt::Write as _;
+use std::fs::{self, File};
+use std::process::{Command, Stdio};
+
+use bench_timer::measure_elapsed_time;
+use rand::Rng;
+
+const N_RUNS: usize = 3;
+
+/**
+ * Runs the problem_01 binary on generated inputs with up to 10^6 queries
+ *  (half updates, half max queries) and prints its throughput as csv.
+ */
+fn main() {
+    let mut rng = rand::thread_rng();
+    let path = env::temp_dir().join("problem_01_throughput.txt");
+
+    println!("n,m,Bytes,Elapsed,QueriesPerSecond,MBPerSecond");
+    for size in [10_000, 100_000, 1_000_000] {
+        let input = generate_input(&mut rng, size);
+        fs::write(&path, &input).expect("Failed to write the input file.");
+
+        let (min_elapsed, _) = measure_elapsed_time(N_RUNS, || {
+            let status = Command::new(env!("CARGO_BIN_EXE_problem_01"))
+                .stdin(File::open(&path).expect("Failed to open the input file."))
+                .stdout(Stdio::null())
+                .status()
+                .expect("Failed to run problem_01.");
+            assert!(status.success(), "problem_01 failed on n = {}", size);
+        });
+        let min_elapsed = min_elapsed as f64 / 1e9;
+
+        println!(
+            "{},{},{},{:.3},{:.0},{:.1}",
+            size,
+            size,
+            input.len(),
+            min_elapsed,
+            size as f64 / min_elapsed,
+            input.len() as f64 / min_elapsed / 1e6
+        );
+    }
+
+    fs::remove_file(&path).expect("Failed to remove the input file.");
+}
+
+/**
+ * Returns an input with an array of size elements and size queries on it.
+ */
+fn generate_input(rng: &mut impl Rng, size: usize) -> String {
+    let mut input = String::new();
+    writeln!(input, "{} {}", size, size).unwrap();
+
+    let elems: Vec<String> = (0..size)
+        .map(|_| rng.gen_range(0..1_000_000_000).to_string())
+        .collect();
+    writeln!(input, "{}", elems.join(" ")).unwrap();
+
+    for _ in 0..size {
+        let a = rng.gen_range(1..=size);
+        let b = rng.gen_range(1..=size);
+        let (left, right) = (a.min(b), a.max(b));
+        if rng.gen_bool(0.5) {
+            let val = rng.gen_range(0..1_000_000_000);
+            writeln!(input, "0 {} {} {}", left, right, val).unwrap();
+        } else {
+            writeln!(input, "1 {} {}", left, right).unwrap();
+        }
+    }
+
+    input
+}
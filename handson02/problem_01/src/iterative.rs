@@ -0,0 +1,188 @@
+use std::cmp;
+use std::ops::RangeBounds;
+
+use segment_tree::to_bounds;
+
+use crate::SegmentTreeError;
+
+/**
+ * Non-recursive segment tree supporting range chmin updates
+ *  and range max queries in O(log n) time.
+ *
+ * Nodes are laid out implicitly in a 2n array: leaves are stored in
+ *  t[n..2n] and node i's children are 2i and 2i + 1, so no padding
+ *  nor ranges need to be stored.
+ */
+#[derive(Debug)]
+pub struct IterativeSegmentTree {
+    n: usize,
+    h: u32,            // Height of the tree
+    t: Vec<i32>,       // Maximum of each node's range, pending updates included
+    pending: Vec<i32>, // Chmin pending on each internal node's children, i32::MAX if none
+}
+
+impl IterativeSegmentTree {
+    /**
+     * Builds a segment tree on the provided data and returns it.
+     */
+    pub fn new(data: &[i32]) -> Self {
+        let n = data.len();
+
+        let mut t = vec![i32::MIN; 2 * n];
+        t[n..].copy_from_slice(data);
+        for i in (1..n).rev() {
+            t[i] = cmp::max(t[2 * i], t[2 * i + 1]);
+        }
+
+        Self {
+            n,
+            h: usize::BITS - n.leading_zeros(),
+            t,
+            pending: vec![i32::MAX; n],
+        }
+    }
+
+    /**
+     * Returns the number of elements in the tree.
+     */
+    pub fn len(&self) -> usize {
+        self.n
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+
+    /**
+     * Substitutes the elements in range with the minimum between themselves and val.
+     */
+    pub fn chmin<R: RangeBounds<usize>>(
+        &mut self,
+        range: R,
+        val: i32,
+    ) -> Result<(), SegmentTreeError> {
+        let (start, end) = to_bounds(range, self.n)?;
+        if start == end {
+            return Ok(());
+        }
+
+        let (mut l, mut r) = (start + self.n, end + self.n);
+        while l < r {
+            if l & 1 == 1 {
+                self.apply(l, val);
+                l += 1;
+            }
+            if r & 1 == 1 {
+                r -= 1;
+                self.apply(r, val);
+            }
+            l >>= 1;
+            r >>= 1;
+        }
+
+        // Update the ancestors of the modified nodes
+        self.build(start + self.n);
+        self.build(end - 1 + self.n);
+
+        Ok(())
+    }
+
+    /**
+     * Returns the maximum element in range.
+     */
+    pub fn max<R: RangeBounds<usize>>(&mut self, range: R) -> Result<i32, SegmentTreeError> {
+        let (start, end) = to_bounds(range, self.n)?;
+        if start == end {
+            return Err(SegmentTreeError::EmptyRange);
+        }
+
+        // Make sure that no update is pending on the nodes we are going to visit
+        self.push(start + self.n);
+        self.push(end - 1 + self.n);
+
+        let mut max = i32::MIN;
+        let (mut l, mut r) = (start + self.n, end + self.n);
+        while l < r {
+            if l & 1 == 1 {
+                max = cmp::max(max, self.t[l]);
+                l += 1;
+            }
+            if r & 1 == 1 {
+                r -= 1;
+                max = cmp::max(max, self.t[r]);
+            }
+            l >>= 1;
+            r >>= 1;
+        }
+
+        Ok(max)
+    }
+
+    /**
+     * Applies a chmin by val to node i, recording it as pending for
+     *  the node's children.
+     */
+    fn apply(&mut self, i: usize, val: i32) {
+        self.t[i] = cmp::min(self.t[i], val);
+        if i < self.n {
+            self.pending[i] = cmp::min(self.pending[i], val);
+        }
+    }
+
+    /**
+     * Recomputes the maximum of every ancestor of leaf p.
+     */
+    fn build(&mut self, mut p: usize) {
+        while p > 1 {
+            p >>= 1;
+            self.t[p] = cmp::min(cmp::max(self.t[2 * p], self.t[2 * p + 1]), self.pending[p]);
+        }
+    }
+
+    /**
+     * Propagates the updates pending on the ancestors of leaf p, from the root down.
+     */
+    fn push(&mut self, p: usize) {
+        for s in (1..=self.h).rev() {
+            let i = p >> s;
+            if i > 0 && self.pending[i] != i32::MAX {
+                self.apply(2 * i, self.pending[i]);
+                self.apply(2 * i + 1, self.pending[i]);
+                self.pending[i] = i32::MAX;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SegmentTree;
+    use rand::Rng;
+
+    #[test]
+    fn test_against_recursive() {
+        let mut rng = rand::thread_rng();
+
+        // Lengths that are not a power of 2 are the interesting ones
+        for n in [1, 2, 7, 100, 129] {
+            let data: Vec<i32> = (0..n).map(|_| rng.gen_range(-1000..1000)).collect();
+            let mut iterative = IterativeSegmentTree::new(&data);
+            let mut recursive = SegmentTree::new(&data);
+
+            for _ in 0..1000 {
+                let a = rng.gen_range(0..n);
+                let b = rng.gen_range(0..n);
+                let (start, end) = (cmp::min(a, b), cmp::max(a, b));
+
+                if rng.gen_bool(0.5) {
+                    let val = rng.gen_range(-1000..1000);
+                    iterative.chmin(start..=end, val).unwrap();
+                    recursive.chmin(start..=end, val).unwrap();
+                } else {
+                    assert_eq!(iterative.max(start..=end), recursive.max(start..=end));
+                }
+            }
+        }
+    }
+}
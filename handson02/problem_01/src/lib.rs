@@ -1,205 +1,848 @@
 use std::cmp;
+use std::error::Error;
+use std::fmt;
+use std::ops::RangeBounds;
+
+use segment_tree::{Monoid, RangeError, Tag};
+
+pub mod iterative;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SegmentTreeError {
+    // Position past the end of the array
+    OutOfBounds { index: usize, len: usize },
+    // Range whose start comes after its (exclusive) end
+    ReversedRange { start: usize, end: usize },
+    // Query on a range with no elements
+    EmptyRange,
+    // Clamp interval with lo > hi
+    InvalidClamp { lo: i32, hi: i32 },
+}
+
+impl fmt::Display for SegmentTreeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SegmentTreeError::OutOfBounds { index, len } => {
+                write!(f, "index {} is out of bounds for length {}", index, len)
+            }
+            SegmentTreeError::ReversedRange { start, end } => {
+                write!(f, "range start {} is after its end {}", start, end)
+            }
+            SegmentTreeError::EmptyRange => write!(f, "cannot query an empty range"),
+            SegmentTreeError::InvalidClamp { lo, hi } => {
+                write!(f, "invalid clamp interval <{}, {}>", lo, hi)
+            }
+        }
+    }
+}
+
+impl Error for SegmentTreeError {}
+
+impl From<RangeError<usize>> for SegmentTreeError {
+    fn from(error: RangeError<usize>) -> Self {
+        match error {
+            RangeError::Reversed { start, end } => SegmentTreeError::ReversedRange { start, end },
+            RangeError::OutOfBounds { index, len } => SegmentTreeError::OutOfBounds { index, len },
+        }
+    }
+}
+
+/**
+ * Broken invariant found by SegmentTree::validate.
+ */
+pub use segment_tree::ValidationError;
 
-#[derive(Copy, Clone, Debug)]
+/**
+ * Read-only view of a node, for debugging purposes.
+ */
+pub type NodeView = segment_tree::NodeView<Data, Clamp>;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct Data {
     min: i32,
     max: i32,
+    second_min: i32,  // Smallest value strictly larger than min, i32::MAX if none
+    second_max: i32,  // Largest value strictly smaller than max, i32::MIN if none
+    min_count: usize, // Number of elements equal to min
+    max_count: usize, // Number of elements equal to max
+    sum: i64,
+    len: usize, // Number of elements
 }
 
-#[derive(Clone, Debug)]
-struct Range {
-    // Perhaps ranges should be usize and not i32
-    // but that way we can't mark invalid ranges as -1
-    start: i32,
-    end: i32,
+impl Data {
+    /**
+     * Returns the minimum element of the range.
+     */
+    pub fn min(&self) -> i32 {
+        self.min
+    }
+
+    /**
+     * Returns the maximum element of the range.
+     */
+    pub fn max(&self) -> i32 {
+        self.max
+    }
+
+    /**
+     * Returns the sum of the range's elements.
+     */
+    pub fn sum(&self) -> i64 {
+        self.sum
+    }
+
+    /**
+     * Returns the data of a range containing only val.
+     */
+    fn leaf(val: i32) -> Self {
+        Data::uniform(val, 1)
+    }
+
+    /**
+     * Returns the data of a range of len elements, all equal to val.
+     */
+    fn uniform(val: i32, len: usize) -> Self {
+        Data {
+            min: val,
+            max: val,
+            second_min: i32::MAX,
+            second_max: i32::MIN,
+            min_count: len,
+            max_count: len,
+            sum: val as i64 * len as i64,
+            len,
+        }
+    }
+
+    /**
+     * Substitutes every element x with min(x, val).
+     *  Only valid if second_max < val.
+     */
+    fn chmin(&mut self, val: i32) {
+        if self.max <= val {
+            // Nothing to do
+            return;
+        }
+
+        // Only the elements equal to max change
+        self.sum -= (self.max as i64 - val as i64) * self.max_count as i64;
+        if self.min == self.max {
+            // All the elements are equal
+            self.min = val;
+        } else if self.second_min == self.max {
+            // Only two distinct values
+            self.second_min = val;
+        }
+        self.max = val;
+    }
+
+    /**
+     * Substitutes every element x with max(x, val).
+     *  Only valid if second_min > val.
+     */
+    fn chmax(&mut self, val: i32) {
+        if self.min >= val {
+            // Nothing to do
+            return;
+        }
+
+        // Only the elements equal to min change
+        self.sum += (val as i64 - self.min as i64) * self.min_count as i64;
+        if self.max == self.min {
+            // All the elements are equal
+            self.max = val;
+        } else if self.second_max == self.min {
+            // Only two distinct values
+            self.second_max = val;
+        }
+        self.min = val;
+    }
 }
 
-#[derive(Clone, Debug)]
-struct Node {
-    data: Data,           // Data contained in the node
-    range: Range,         // The range this node refers to
-    pending: Option<i32>, // Optional pending update on this node
+impl Monoid for Data {
+    /**
+     * Returns the data of an empty range.
+     */
+    fn identity() -> Self {
+        Data {
+            min: i32::MAX,
+            max: i32::MIN,
+            second_min: i32::MAX,
+            second_max: i32::MIN,
+            min_count: 0,
+            max_count: 0,
+            sum: 0,
+            len: 0,
+        }
+    }
+
+    /**
+     * Combines two Data objects a and b into one.
+     */
+    fn combine(a: Data, b: Data) -> Data {
+        let (min, second_min, min_count) = match a.min.cmp(&b.min) {
+            cmp::Ordering::Equal => (
+                a.min,
+                cmp::min(a.second_min, b.second_min),
+                a.min_count + b.min_count,
+            ),
+            cmp::Ordering::Less => (a.min, cmp::min(a.second_min, b.min), a.min_count),
+            cmp::Ordering::Greater => (b.min, cmp::min(a.min, b.second_min), b.min_count),
+        };
+
+        let (max, second_max, max_count) = match a.max.cmp(&b.max) {
+            cmp::Ordering::Equal => (
+                a.max,
+                cmp::max(a.second_max, b.second_max),
+                a.max_count + b.max_count,
+            ),
+            cmp::Ordering::Greater => (a.max, cmp::max(a.second_max, b.max), a.max_count),
+            cmp::Ordering::Less => (b.max, cmp::max(a.max, b.second_max), b.max_count),
+        };
+
+        Data {
+            min,
+            max,
+            second_min,
+            second_max,
+            min_count,
+            max_count,
+            sum: a.sum + b.sum,
+            len: a.len + b.len,
+        }
+    }
+}
+
+/**
+ * Update x = min(max(x, lo), hi).
+ * Chmin, chmax and assign updates are all special cases
+ *  of it, and so are their compositions.
+ */
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Clamp {
+    lo: i32,
+    hi: i32,
+}
+
+impl fmt::Display for Clamp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.lo, self.hi) {
+            (lo, hi) if lo == hi => write!(f, "assign {}", lo),
+            (i32::MIN, hi) => write!(f, "chmin {}", hi),
+            (lo, i32::MAX) => write!(f, "chmax {}", lo),
+            (lo, hi) => write!(f, "clamp {} {}", lo, hi),
+        }
+    }
+}
+
+impl Clamp {
+    /**
+     * Returns the update equivalent to applying self first and then next.
+     */
+    fn then(self, next: Clamp) -> Clamp {
+        Clamp {
+            lo: cmp::min(cmp::max(self.lo, next.lo), next.hi),
+            hi: cmp::min(cmp::max(self.hi, next.lo), next.hi),
+        }
+    }
 }
 
-#[derive(Debug)]
+impl Tag<Data> for Clamp {
+    fn apply(&self, data: Data) -> Data {
+        if data.len == 0 {
+            // Padding stays such
+            return data;
+        }
+
+        if self.lo == self.hi {
+            return Data::uniform(self.lo, data.len);
+        }
+
+        let mut data = data;
+        data.chmax(self.lo);
+        data.chmin(self.hi);
+
+        data
+    }
+
+    fn compose(&self, older: Self) -> Self {
+        older.then(*self)
+    }
+
+    fn is_noop(&self, data: &Data) -> bool {
+        data.min >= self.lo && data.max <= self.hi
+    }
+
+    fn can_apply(&self, data: &Data) -> bool {
+        // An assignment makes all the elements equal, otherwise only the
+        // minimum and the maximum elements may be affected (see chmin and chmax)
+        self.lo == self.hi || (data.second_min > self.lo && data.second_max < self.hi)
+    }
+}
+
+/**
+ * Segment tree supporting range chmin, chmax and assign updates and
+ *  range min/max/sum queries in amortized O(log^2 n) time
+ *  ("segment tree beats", Ji driver), O(log n) with chmin updates only.
+ *
+ * Every node stores the maximum of its range, the largest value below it
+ *  and the number of occurrences of the maximum (and the same for the minimum).
+ * A chmin with val such that second_max < val < max only affects the elements equal
+ *  to max, so the node can be updated in O(1) and the update left pending,
+ *  which is what Clamp::can_apply tells segment_tree::SegmentTree.
+ *
+ * The leaves span a power of 2 capacity, so that elements can be appended
+ *  in O(log n) time and the capacity doubled (in amortized O(1) time) once
+ *  it runs out.
+ */
+#[derive(Clone, Debug)]
 pub struct SegmentTree {
-    nodes: Vec<Node>,
+    tree: segment_tree::SegmentTree<Data, Clamp>,
 }
 
 impl SegmentTree {
     /**
      * Builds a segment tree on the provided data and returns it.
      */
-    pub fn new(data: &Vec<i32>) -> Self {
-        // Add padding in case the data vector's length is not a power of 2
-        let smallest_exp = (data.len() as f64).log2().ceil() as u32;
-        let tree_size = 2 * usize::pow(2, smallest_exp) - 1;
+    pub fn new(data: &[i32]) -> Self {
+        let leaves: Vec<Data> = data.iter().map(|&val| Data::leaf(val)).collect();
 
-        let mut nodes: Vec<Node> = vec![
-            Node {
-                range: Range { start: -1, end: -1 },
-                data: Data {
-                    // Fill with dummy data
-                    min: i32::MAX,
-                    max: i32::MIN,
-                },
-                pending: None
-            };
-            tree_size
-        ];
+        Self {
+            tree: segment_tree::SegmentTree::new(&leaves),
+        }
+    }
 
-        SegmentTree::build_tree(&mut nodes, 0, data, 0, (data.len() - 1) as i32);
+    /**
+     * Returns the number of elements in the tree.
+     */
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /**
+     * Returns the number of elements the tree can hold before having to grow.
+     */
+    pub fn capacity(&self) -> usize {
+        self.tree.capacity()
+    }
 
-        Self { nodes }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
     }
 
     /**
-     * Substitutes the elements of the <start, end> (inclusive) range
-     *  with the minimum between themselves and val.
+     * Substitutes the elements in range with the minimum between themselves and val.
      */
-    pub fn range_update(&mut self, i: usize, val: i32, start: i32, end: i32) -> Data {
-        if i >= self.nodes.len() {
-            panic!("Index out of bounds.");
-        }
+    pub fn chmin<R: RangeBounds<usize>>(
+        &mut self,
+        range: R,
+        val: i32,
+    ) -> Result<(), SegmentTreeError> {
+        self.update(
+            range,
+            Clamp {
+                lo: i32::MIN,
+                hi: val,
+            },
+        )
+    }
 
-        if start > self.nodes[i].range.end || end < self.nodes[i].range.start {
-            //No overlap
-            return self.nodes[i].data;
-        }
+    /**
+     * Substitutes the elements in range with the maximum between themselves and val.
+     */
+    pub fn chmax<R: RangeBounds<usize>>(
+        &mut self,
+        range: R,
+        val: i32,
+    ) -> Result<(), SegmentTreeError> {
+        self.update(
+            range,
+            Clamp {
+                lo: val,
+                hi: i32::MAX,
+            },
+        )
+    }
 
-        self.propagate_pending_update(i);
+    /**
+     * Substitutes the elements in range with val.
+     */
+    pub fn assign<R: RangeBounds<usize>>(
+        &mut self,
+        range: R,
+        val: i32,
+    ) -> Result<(), SegmentTreeError> {
+        self.update(range, Clamp { lo: val, hi: val })
+    }
 
-        if self.nodes[i].range.start >= start && self.nodes[i].range.end <= end {
-            // Total overlap
-            if self.nodes[i].range.start == self.nodes[i].range.end {
-                // Update current node and propagate to immediate children
-                self.nodes[i].pending = Some(val);
-                self.propagate_pending_update(i);
-                return self.nodes[i].data;
-            }
+    /**
+     * Clamps the elements in range to the <lo, hi> (inclusive) interval.
+     */
+    pub fn clamp<R: RangeBounds<usize>>(
+        &mut self,
+        range: R,
+        lo: i32,
+        hi: i32,
+    ) -> Result<(), SegmentTreeError> {
+        if lo > hi {
+            return Err(SegmentTreeError::InvalidClamp { lo, hi });
         }
 
-        // Partial overlap
-        let left: Data = self.range_update(2 * i + 1, val, start, end);
-        let right: Data = self.range_update(2 * i + 2, val, start, end);
+        self.update(range, Clamp { lo, hi })
+    }
 
-        self.nodes[i].data = SegmentTree::combine_data(left, right);
+    /**
+     * Returns the maximum element in range.
+     */
+    pub fn max<R: RangeBounds<usize>>(&mut self, range: R) -> Result<i32, SegmentTreeError> {
+        Ok(self.query(range)?.max)
+    }
 
-        self.nodes[i].data
+    /**
+     * Returns the minimum element in range.
+     */
+    pub fn min<R: RangeBounds<usize>>(&mut self, range: R) -> Result<i32, SegmentTreeError> {
+        Ok(self.query(range)?.min)
     }
 
     /**
-     * Returns the maximum element in the <start, end> (inclusive) range.
+     * Returns both the minimum and the maximum (and the sum) of range
+     *  in a single traversal.
      */
-    pub fn range_max(&mut self, i: usize, start: i32, end: i32) -> i32 {
-        if i >= self.nodes.len() {
-            panic!("Index out of bounds.")
-        }
+    pub fn min_max<R: RangeBounds<usize>>(&mut self, range: R) -> Result<Data, SegmentTreeError> {
+        self.query(range)
+    }
 
-        if start > self.nodes[i].range.end || end < self.nodes[i].range.start {
-            //No overlap, return dummy data
-            return i32::MIN;
-        }
+    /**
+     * Returns the sum of the elements in range.
+     */
+    pub fn sum<R: RangeBounds<usize>>(&mut self, range: R) -> Result<i64, SegmentTreeError> {
+        Ok(self.query(range)?.sum)
+    }
 
-        self.propagate_pending_update(i);
+    /**
+     * Returns the element at position pos.
+     */
+    pub fn get(&mut self, pos: usize) -> Result<i32, SegmentTreeError> {
+        Ok(self.tree.get(pos)?.max)
+    }
 
-        if start <= self.nodes[i].range.start && end >= self.nodes[i].range.end {
-            // Total overlap
-            return self.nodes[i].data.max;
-        }
+    /**
+     * Replaces the element at position pos with val.
+     */
+    pub fn set(&mut self, pos: usize, val: i32) -> Result<(), SegmentTreeError> {
+        self.update_with(pos, |_| val)?;
 
-        //Partial overlap
-        let left: i32 = self.range_max(2 * i + 1, start, end);
-        let right: i32 = self.range_max(2 * i + 2, start, end);
+        Ok(())
+    }
 
-        cmp::max(left, right)
+    /**
+     * Replaces the element x at position pos with f(x) and returns the new value.
+     */
+    pub fn update_with<F: FnOnce(i32) -> i32>(
+        &mut self,
+        pos: usize,
+        f: F,
+    ) -> Result<i32, SegmentTreeError> {
+        let data = self.tree.update_with(pos, |data| Data::leaf(f(data.max)))?;
+
+        Ok(data.max)
     }
 
     /**
-     * Recursively build the segment tree
+     * Returns the smallest position i >= start such that pred(max(start..=i))
+     *  holds, or None if there's none.
+     *
+     * pred must be monotone: once it holds for a range, it must hold for
+     *  every range extending it to the right. For example, with
+     *  pred = |max| max > x the result is the first position >= start whose
+     *  element exceeds x, while with pred = |max| max >= x it's the (exclusive)
+     *  end of the longest range starting at start whose maximum is below x.
      */
-    fn build_tree(nodes: &mut Vec<Node>, i: usize, data: &Vec<i32>, start: i32, end: i32) -> Data {
-        if i >= nodes.len() || start == -1 || end == -1 {
-            panic!("Invalid parameters.");
-        }
+    pub fn find_first<F: FnMut(i32) -> bool>(
+        &mut self,
+        start: usize,
+        mut pred: F,
+    ) -> Result<Option<usize>, SegmentTreeError> {
+        Ok(self.tree.find_first(start, |data| pred(data.max))?)
+    }
 
-        // Leaf
-        if start == end {
-            nodes[i] = Node {
-                data: Data {
-                    //Leaves have min == max
-                    min: data[start as usize],
-                    max: data[start as usize],
-                },
-                range: Range { start, end },
-                pending: None,
-            };
+    /**
+     * Returns the largest position i <= end such that pred(max(i..=end))
+     *  holds, or None if there's none.
+     *
+     * pred must be monotone: once it holds for a range, it must hold for
+     *  every range extending it to the left.
+     */
+    pub fn find_last<F: FnMut(i32) -> bool>(
+        &mut self,
+        end: usize,
+        mut pred: F,
+    ) -> Result<Option<usize>, SegmentTreeError> {
+        Ok(self.tree.find_last(end, |data| pred(data.max))?)
+    }
+
+    /**
+     * Appends val to the end of the array.
+     */
+    pub fn push(&mut self, val: i32) {
+        self.tree.push(Data::leaf(val));
+    }
 
-            return nodes[i].data;
+    /**
+     * Appends every element of iter to the end of the array.
+     */
+    pub fn extend<I: IntoIterator<Item = i32>>(&mut self, iter: I) {
+        for val in iter {
+            self.push(val);
         }
+    }
 
-        //Non-leaf node
-        let m: i32 = ((start as f64 + end as f64) / 2.0).floor() as i32;
-        let left = SegmentTree::build_tree(nodes, 2 * i + 1, data, start, m);
-        let right = SegmentTree::build_tree(nodes, 2 * i + 2, data, m + 1, end);
+    /**
+     * Removes the last element of the array and returns it, or None if it's empty.
+     *  The capacity is left unchanged.
+     */
+    pub fn pop(&mut self) -> Option<i32> {
+        self.tree.pop().map(|data| data.max)
+    }
 
-        // Create and insert the node
-        nodes[i] = Node {
-            data: SegmentTree::combine_data(left, right),
-            range: Range { start, end },
-            pending: None,
-        };
+    /**
+     * Returns a view of every node holding at least one element, in preorder.
+     */
+    pub fn node_views(&self) -> Vec<NodeView> {
+        self.tree.node_views()
+    }
 
-        nodes[i].data
+    /**
+     * Returns the tree in Graphviz DOT format, with every node's range,
+     *  data and pending update, nodes only made of padding excluded.
+     */
+    pub fn to_dot(&self) -> String {
+        self.tree
+            .to_dot(|data| format!("min {} | max {} | sum {}", data.min, data.max, data.sum))
     }
 
     /**
-     * Combines two Data objects a and b into one.
+     * Walks the whole tree checking its invariants (see
+     *  segment_tree::SegmentTree::validate_with), and that
+     *  every leaf holds a single element.
      */
-    fn combine_data(a: Data, b: Data) -> Data {
-        Data {
-            min: cmp::min(a.min, b.min),
-            max: cmp::max(a.max, b.max),
-        }
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        self.tree.validate_with(|data| {
+            if *data != Data::leaf(data.max) {
+                return Err(format!("leaf holds {:?}, not a single element", data));
+            }
+
+            Ok(())
+        })
+    }
+
+    /**
+     * Applies clamp to the elements in range.
+     */
+    fn update<R: RangeBounds<usize>>(
+        &mut self,
+        range: R,
+        clamp: Clamp,
+    ) -> Result<(), SegmentTreeError> {
+        self.tree.range_update(range, clamp)?;
+
+        Ok(())
     }
 
     /**
-     * Applies any pending update on node i and propagates
-     * it to the node's children.
+     * Returns the data of the elements in range, which must not be empty.
      */
-    fn propagate_pending_update(&mut self, i: usize) {
-        if i >= self.nodes.len() {
-            panic!("Index out of bounds.");
+    fn query<R: RangeBounds<usize>>(&mut self, range: R) -> Result<Data, SegmentTreeError> {
+        let data = self.tree.range_query(range)?;
+        if data.len == 0 {
+            return Err(SegmentTreeError::EmptyRange);
         }
 
-        let mut node = &mut self.nodes[i];
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn test_point_operations() {
+        let mut truth: Vec<i32> = vec![5, 1, 9, 3, 7, 2, 8];
+        let mut seg_tree = SegmentTree::new(&truth);
+
+        seg_tree.chmin(1..=5, 4).unwrap();
+        for x in &mut truth[1..=5] {
+            *x = cmp::min(*x, 4);
+        }
 
-        match node.pending {
-            Some(val) => {
-                node.pending = None;
+        seg_tree.set(3, 10).unwrap();
+        truth[3] = 10;
+        assert_eq!(seg_tree.update_with(6, |x| x - 5), Ok(3));
+        truth[6] -= 5;
 
-                // Update ith node
-                node.data.min = cmp::min(node.data.min, val);
-                node.data.max = cmp::min(node.data.max, val);
+        for (pos, val) in truth.iter().enumerate() {
+            assert_eq!(seg_tree.get(pos), Ok(*val));
+        }
+        assert_eq!(seg_tree.max(..), Ok(10));
+        assert_eq!(seg_tree.max(4..7), Ok(4));
+    }
 
-                //Set pending update on children
-                let left_i = 2 * i + 1;
-                if left_i < self.nodes.len() {
-                    self.nodes[left_i].pending = match self.nodes[left_i].pending {
-                        Some(old_val) => Some(cmp::min(old_val, val)),
-                        None => Some(val),
+    #[test]
+    fn test_mixed_updates() {
+        let mut rng = rand::thread_rng();
+        let mut truth: Vec<i32> = (0..200).map(|_| rng.gen_range(-100..100)).collect();
+        let mut seg_tree = SegmentTree::new(&truth);
+
+        for _ in 0..2000 {
+            let a = rng.gen_range(0..truth.len());
+            let b = rng.gen_range(0..truth.len());
+            let (start, end) = (cmp::min(a, b), cmp::max(a, b));
+            let val = rng.gen_range(-100..100);
+
+            match rng.gen_range(0..5) {
+                0 => {
+                    seg_tree.chmin(start..=end, val).unwrap();
+                    for x in &mut truth[start..=end] {
+                        *x = cmp::min(*x, val);
+                    }
+                }
+                1 => {
+                    seg_tree.chmax(start..=end, val).unwrap();
+                    for x in &mut truth[start..=end] {
+                        *x = cmp::max(*x, val);
+                    }
+                }
+                2 => {
+                    seg_tree.assign(start..=end, val).unwrap();
+                    for x in &mut truth[start..=end] {
+                        *x = val;
                     }
                 }
+                3 => {
+                    let hi = val + rng.gen_range(0..50);
+                    seg_tree.clamp(start..=end, val, hi).unwrap();
+                    for x in &mut truth[start..=end] {
+                        *x = (*x).clamp(val, hi);
+                    }
+                }
+                _ => {
+                    let data = seg_tree.min_max(start..=end).unwrap();
+                    let expected_min = *truth[start..=end].iter().min().unwrap();
+                    let expected_max = *truth[start..=end].iter().max().unwrap();
+                    let expected_sum: i64 = truth[start..=end].iter().map(|&x| x as i64).sum();
+                    assert_eq!(data.min(), expected_min);
+                    assert_eq!(data.max(), expected_max);
+                    assert_eq!(data.sum(), expected_sum);
+                }
+            }
+
+            assert_eq!(seg_tree.validate(), Ok(()));
+        }
+
+        for (pos, val) in truth.iter().enumerate() {
+            assert_eq!(seg_tree.get(pos), Ok(*val));
+        }
+    }
 
-                let right_i = 2 * i + 2;
-                if right_i < self.nodes.len() {
-                    self.nodes[right_i].pending = match self.nodes[right_i].pending {
-                        Some(old_val) => Some(cmp::min(old_val, val)),
-                        None => Some(val),
+    #[test]
+    fn test_chmin_max_sum() {
+        let mut rng = rand::thread_rng();
+        let mut truth: Vec<i32> = (0..200).map(|_| rng.gen_range(-1000..1000)).collect();
+        let mut seg_tree = SegmentTree::new(&truth);
+
+        for _ in 0..2000 {
+            let a = rng.gen_range(0..truth.len());
+            let b = rng.gen_range(0..truth.len());
+            let (start, end) = (cmp::min(a, b), cmp::max(a, b));
+
+            match rng.gen_range(0..4) {
+                0 => {
+                    let val = rng.gen_range(-1000..1000);
+                    seg_tree.chmin(start..=end, val).unwrap();
+                    for x in &mut truth[start..=end] {
+                        *x = cmp::min(*x, val);
                     }
                 }
+                1 => {
+                    let expected = *truth[start..=end].iter().max().unwrap();
+                    assert_eq!(seg_tree.max(start..=end).unwrap(), expected);
+                }
+                2 => {
+                    let expected_min = *truth[start..=end].iter().min().unwrap();
+                    let expected_max = *truth[start..=end].iter().max().unwrap();
+                    assert_eq!(seg_tree.min(start..=end).unwrap(), expected_min);
+
+                    let data = seg_tree.min_max(start..=end).unwrap();
+                    assert_eq!((data.min(), data.max()), (expected_min, expected_max));
+                }
+                _ => {
+                    let expected: i64 = truth[start..=end].iter().map(|&x| x as i64).sum();
+                    assert_eq!(seg_tree.sum(start..=end).unwrap(), expected);
+                }
             }
-            None => { /*Nothing to propagate*/ }
         }
     }
+
+    #[test]
+    fn test_find() {
+        let mut rng = rand::thread_rng();
+        let mut truth: Vec<i32> = (0..200).map(|_| rng.gen_range(-1000..1000)).collect();
+        let mut seg_tree = SegmentTree::new(&truth);
+
+        for _ in 0..2000 {
+            let pos = rng.gen_range(0..truth.len());
+            let val = rng.gen_range(-1000..1000);
+
+            match rng.gen_range(0..3) {
+                0 => {
+                    // Leave chmin updates pending on the nodes to descend into
+                    seg_tree.chmin(pos.., val).unwrap();
+                    for x in &mut truth[pos..] {
+                        *x = cmp::min(*x, val);
+                    }
+                }
+                1 => {
+                    let expected = (pos..truth.len()).find(|&i| truth[i] > val);
+                    assert_eq!(seg_tree.find_first(pos, |max| max > val), Ok(expected));
+                }
+                _ => {
+                    let expected = (0..=pos).rev().find(|&i| truth[i] > val);
+                    assert_eq!(seg_tree.find_last(pos, |max| max > val), Ok(expected));
+                }
+            }
+        }
+
+        // Longest range starting at 2 with maximum below 4
+        let mut seg_tree = SegmentTree::new(&[5, 1, 2, 3, 7, 0]);
+        assert_eq!(seg_tree.find_first(2, |max| max >= 4), Ok(Some(4)));
+        assert_eq!(seg_tree.find_first(5, |max| max >= 4), Ok(None));
+        assert_eq!(
+            seg_tree.find_last(6, |max| max >= 4),
+            Err(SegmentTreeError::OutOfBounds { index: 6, len: 6 })
+        );
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let mut seg_tree = SegmentTree::new(&[5, 1, 9]);
+        seg_tree.chmin(.., 4).unwrap();
+
+        let dot = seg_tree.to_dot();
+        assert!(dot.starts_with("digraph SegmentTree {"));
+        assert!(dot.contains(
+            "n0 [label=\"[0, 3] (1 padding) | min 1 | max 4 | sum 9 | pending: none\"];"
+        ));
+        // Node 2 and its only real child have different ranges
+        assert!(dot.contains(
+            "n2 [label=\"[2, 3] (1 padding) | min 4 | max 4 | sum 4 | pending: none\"];"
+        ));
+        assert!(dot.contains("n5 [label=\"[2, 2] | min 4 | max 4 | sum 4 | pending: none\"];"));
+        assert!(dot.contains("n1 [label=\"[0, 1] | min 1 | max 4 | sum 5 | pending: chmin 4\"];"));
+        // Leaves below a pending update are out of date
+        assert!(dot.contains("n3 [label=\"[0, 0] | min 5 | max 5 | sum 5 | pending: none\"];"));
+        assert!(dot.contains("n1 -> n3;"));
+        // The leaf at position 3 is padding
+        assert!(!dot.contains("n6"));
+    }
+
+    #[test]
+    fn test_push_pop() {
+        let mut rng = rand::thread_rng();
+        let mut truth: Vec<i32> = Vec::new();
+        let mut seg_tree = SegmentTree::new(&truth);
+
+        for _ in 0..3000 {
+            let val = rng.gen_range(-100..100);
+            match rng.gen_range(0..6) {
+                0 | 1 => {
+                    seg_tree.push(val);
+                    truth.push(val);
+                }
+                2 => assert_eq!(seg_tree.pop(), truth.pop()),
+                3 => {
+                    let vals: Vec<i32> = (0..rng.gen_range(0..10)).map(|_| val).collect();
+                    seg_tree.extend(vals.iter().copied());
+                    truth.extend(vals);
+                }
+                _ if !truth.is_empty() => {
+                    // Leave updates pending on the elements that are going to be popped
+                    let start = rng.gen_range(0..truth.len());
+                    if rng.gen_bool(0.5) {
+                        seg_tree.assign(start.., val).unwrap();
+                        truth[start..].fill(val);
+                    } else {
+                        seg_tree.chmin(start.., val).unwrap();
+                        for x in &mut truth[start..] {
+                            *x = cmp::min(*x, val);
+                        }
+                    }
+
+                    let data = seg_tree.min_max(..).unwrap();
+                    assert_eq!(data.max(), *truth.iter().max().unwrap());
+                    assert_eq!(data.min(), *truth.iter().min().unwrap());
+                    assert_eq!(data.sum(), truth.iter().map(|&x| x as i64).sum::<i64>());
+                }
+                _ => {}
+            }
+
+            assert_eq!(seg_tree.len(), truth.len());
+            assert!(seg_tree.capacity() >= truth.len());
+            assert_eq!(seg_tree.validate(), Ok(()));
+        }
+
+        for (pos, val) in truth.iter().enumerate() {
+            assert_eq!(seg_tree.get(pos), Ok(*val));
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut seg_tree = SegmentTree::new(&[3, 1, 4, 1, 5]);
+        seg_tree.chmin(.., 3).unwrap();
+        assert_eq!(seg_tree.validate(), Ok(()));
+
+        // Pending clamps must keep every node consistent
+        seg_tree.clamp(1..4, 2, 3).unwrap();
+        seg_tree.push(9);
+        seg_tree.assign(3.., 0).unwrap();
+        assert_eq!(seg_tree.validate(), Ok(()));
+        for (pos, val) in [3, 2, 3, 0, 0, 0].into_iter().enumerate() {
+            assert_eq!(seg_tree.get(pos), Ok(val));
+        }
+        assert_eq!(seg_tree.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_range_errors() {
+        let mut seg_tree = SegmentTree::new(&[3, 1, 4, 1, 5]);
+
+        assert_eq!(seg_tree.max(1..4), Ok(4));
+        assert_eq!(seg_tree.max(4..), Ok(5));
+        assert_eq!(
+            seg_tree.max(3..=5),
+            Err(SegmentTreeError::OutOfBounds { index: 5, len: 5 })
+        );
+        let (start, end) = (4, 2);
+        assert_eq!(
+            seg_tree.chmin(start..end, 0),
+            Err(SegmentTreeError::ReversedRange { start: 4, end: 2 })
+        );
+        assert_eq!(seg_tree.min(2..2), Err(SegmentTreeError::EmptyRange));
+        assert_eq!(seg_tree.chmin(2..2, 0), Ok(()));
+        assert_eq!(
+            seg_tree.clamp(.., 3, 2),
+            Err(SegmentTreeError::InvalidClamp { lo: 3, hi: 2 })
+        );
+        assert_eq!(
+            seg_tree.get(5),
+            Err(SegmentTreeError::OutOfBounds { index: 5, len: 5 })
+        );
+
+        let mut empty = SegmentTree::new(&[]);
+        assert!(empty.is_empty());
+        assert_eq!(empty.max(..), Err(SegmentTreeError::EmptyRange));
+    }
 }
@@ -1,53 +1,68 @@
-use std::io;
-mod lib;
+use problem_01::SegmentTree;
+use std::env;
+use std::error::Error;
+use std::io::{self, BufWriter, Write};
+use std::process;
+use token_reader::TokenReader;
+
+mod repl;
+
+const USAGE: &str = "Usage: problem_01 [--repl] < input.txt
+
+    --repl  Start an interactive session instead of reading queries from stdin";
 
 fn main() {
-    let mut buffer = String::new();
-    let stdin = io::stdin();
+    let result = match env::args().nth(1).as_deref() {
+        None => run(),
+        Some("--repl") => repl::run(),
+        Some(_) => {
+            eprintln!("{}", USAGE);
+            process::exit(1);
+        }
+    };
 
-    // Read n and m
-    stdin.read_line(&mut buffer).expect("Failed to read line.");
-    let tmp: Vec<i32> = buffer
-        .split_whitespace()
-        .map(|x| x.parse().expect("Not an integer!"))
-        .collect();
-    //let n: i32 = *tmp.get(0).expect("Missing input parameters."); // Unnecessary
-    let m: i32 = *tmp.get(1).expect("Missing input parameters.");
+    if let Err(why) = result {
+        eprintln!("Error: {}", why);
+        process::exit(1);
+    }
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+    // The whole input is read at once and the answers are buffered,
+    // so that neither stdin nor stdout is locked once per query
+    let mut reader = TokenReader::from_reader(io::stdin().lock())?;
+    let mut out = BufWriter::new(io::stdout().lock());
 
-    buffer.clear();
+    // Read n and m
+    let n: usize = reader.next("array length n")?;
+    let m: usize = reader.next("number of queries m")?;
 
     // Read input array
-    stdin.read_line(&mut buffer).expect("Failed to read line.");
-    let elems: Vec<i32> = buffer
-        .split_whitespace()
-        .map(|x| x.parse().expect("Not an integer!"))
-        .collect();
+    let elems: Vec<i32> = reader.next_vec(n, "array element")?;
 
     // Create a segment tree over the array of elements
-    let mut seg_tree = lib::SegmentTree::new(&elems);
+    let mut seg_tree = SegmentTree::new(&elems);
 
     // Read queries
     for _i in 0..m {
-        buffer.clear();
-
-        stdin.read_line(&mut buffer).expect("Failed to read line.");
-        let tmp: Vec<i32> = buffer
-            .split_whitespace()
-            .map(|x| x.parse().expect("Not an integer!"))
-            .collect();
-        let query_type: i32 = *tmp.first().expect("Malformed query.");
-        let left: i32 = *tmp.get(1).expect("Malformed query.") - 1;
-        let right: i32 = *tmp.get(2).expect("Malformed query.") - 1;
+        let query_type: u8 = reader.next_in(0..=1, "query type")?;
+        // Queries use 1-based indices
+        let left = reader.next_in(1..=n, "query start")? - 1;
+        let right = reader.next_in(left + 1..=n, "query end")? - 1;
 
         match query_type {
             0 => {
-                let val: i32 = *tmp.get(3).expect("Malformed query.");
-                seg_tree.range_update(0, val, left, right);
+                let val: i32 = reader.next("update value")?;
+                seg_tree.chmin(left..=right, val)?;
             }
-            1 => {
-                println!("{}", seg_tree.range_max(0, left, right));
+            _ => {
+                let max = seg_tree.max(left..=right)?;
+                writeln!(out, "{}", max)?;
             }
-            _ => panic!("Unrecognized query type."),
         }
     }
+    reader.finish()?;
+    out.flush()?;
+
+    Ok(())
 }
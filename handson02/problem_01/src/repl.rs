@@ -0,0 +1,374 @@
+use std::collections::VecDeque;
+use std::error::Error;
+use std::io::{self, BufRead, Write};
+
+use problem_01::{NodeView, SegmentTree};
+
+const HELP: &str = "\
+Commands (positions are 1-based, ranges inclusive):
+    load <v1> <v2> ...   Build a new tree on the given elements
+    chmin <l> <r> <v>    Substitute each element in <l, r> with min(element, v)
+    max <l> <r>          Print the maximum element in <l, r>
+    min <l> <r>          Print the minimum element in <l, r>
+    dump                 Print every node's range, data and pending update
+    undo                 Revert the last load or chmin (up to 100 of them)
+    help                 Print this message
+    quit                 Exit (as does end of input)";
+
+// Number of updates that can be undone
+const HISTORY_LIMIT: usize = 100;
+
+/**
+ * Command of the interactive mode, with the positions as typed (1-based).
+ */
+#[derive(Debug, PartialEq, Eq)]
+enum Command {
+    Load(Vec<i32>),
+    Chmin { l: i32, r: i32, val: i32 },
+    Max { l: i32, r: i32 },
+    Min { l: i32, r: i32 },
+    Dump,
+    Undo,
+    Help,
+    Quit,
+    Empty,
+}
+
+/**
+ * What it takes to revert an update.
+ */
+enum Inverse {
+    // Elements before the load
+    Load(Vec<i32>),
+    // Positions changed by the chmin, along with their old values
+    Chmin(Vec<(usize, i32)>),
+}
+
+/**
+ * Tree being worked on, along with the updates that can be undone.
+ */
+struct Session {
+    seg_tree: SegmentTree,
+    history: VecDeque<Inverse>, // Oldest first
+}
+
+/**
+ * Runs the interactive mode, reading one command per line from stdin.
+ */
+pub fn run() -> Result<(), Box<dyn Error>> {
+    let mut session = Session::new();
+
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+    let mut line = String::new();
+    loop {
+        write!(stdout, "> ")?;
+        stdout.flush()?;
+
+        line.clear();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            // End of input
+            writeln!(stdout)?;
+            return Ok(());
+        }
+
+        match parse_command(&line).and_then(|command| match command {
+            Command::Quit => Ok(None),
+            command => session.execute(command).map(Some),
+        }) {
+            Ok(None) => return Ok(()),
+            Ok(Some(output)) => write!(stdout, "{}", output)?,
+            Err(why) => writeln!(stdout, "Error: {}", why)?,
+        }
+    }
+}
+
+/**
+ * Parses a line typed by the user.
+ */
+fn parse_command(line: &str) -> Result<Command, Box<dyn Error>> {
+    let args: Vec<&str> = line.split_whitespace().collect();
+    let command = match args.as_slice() {
+        [] => Command::Empty,
+        ["quit"] | ["exit"] => Command::Quit,
+        ["help"] => Command::Help,
+        ["load", vals @ ..] => Command::Load(parse_all(vals)?),
+        ["chmin", l, r, v] => Command::Chmin {
+            l: parse(l)?,
+            r: parse(r)?,
+            val: parse(v)?,
+        },
+        ["max", l, r] => Command::Max {
+            l: parse(l)?,
+            r: parse(r)?,
+        },
+        ["min", l, r] => Command::Min {
+            l: parse(l)?,
+            r: parse(r)?,
+        },
+        ["dump"] => Command::Dump,
+        ["undo"] => Command::Undo,
+        [command, ..] => {
+            return Err(format!(
+                "Unknown command or wrong arguments '{}', try 'help'.",
+                command
+            )
+            .into())
+        }
+    };
+
+    Ok(command)
+}
+
+impl Session {
+    fn new() -> Self {
+        Self {
+            seg_tree: SegmentTree::new(&Vec::new()),
+            history: VecDeque::new(),
+        }
+    }
+
+    /**
+     * Runs command and returns what it prints, one line per result.
+     */
+    fn execute(&mut self, command: Command) -> Result<String, Box<dyn Error>> {
+        let mut output = String::new();
+        match command {
+            Command::Empty | Command::Quit => {}
+            Command::Help => output = format!("{}\n", HELP),
+            Command::Load(elems) => {
+                let old_elems = self.elements()?;
+                self.seg_tree = SegmentTree::new(&elems);
+                self.record(Inverse::Load(old_elems));
+                output = format!("Loaded {} elements.\n", self.seg_tree.len());
+            }
+            Command::Chmin { l, r, val } => {
+                let (left, right) = self.to_range(l, r)?;
+                let changed = self.changed_by_chmin(left, right, val)?;
+                self.seg_tree.chmin(left..=right, val)?;
+                self.record(Inverse::Chmin(changed));
+            }
+            Command::Max { l, r } => {
+                let (left, right) = self.to_range(l, r)?;
+                output = format!("{}\n", self.seg_tree.max(left..=right)?);
+            }
+            Command::Min { l, r } => {
+                let (left, right) = self.to_range(l, r)?;
+                output = format!("{}\n", self.seg_tree.min(left..=right)?);
+            }
+            Command::Dump => {
+                for view in self.seg_tree.node_views() {
+                    output += &format_node(&view);
+                    output.push('\n');
+                }
+            }
+            Command::Undo => match self.history.pop_back() {
+                Some(Inverse::Load(elems)) => self.seg_tree = SegmentTree::new(&elems),
+                Some(Inverse::Chmin(changed)) => {
+                    for (pos, val) in changed {
+                        self.seg_tree.set(pos, val)?;
+                    }
+                }
+                None => return Err("Nothing to undo.".into()),
+            },
+        }
+
+        Ok(output)
+    }
+
+    /**
+     * Records how to revert the last update, forgetting the oldest one if needed.
+     */
+    fn record(&mut self, inverse: Inverse) {
+        if self.history.len() == HISTORY_LIMIT {
+            self.history.pop_front();
+        }
+        self.history.push_back(inverse);
+    }
+
+    /**
+     * Returns the elements in the tree.
+     */
+    fn elements(&mut self) -> Result<Vec<i32>, Box<dyn Error>> {
+        let mut elems = Vec::with_capacity(self.seg_tree.len());
+        for pos in 0..self.seg_tree.len() {
+            elems.push(self.seg_tree.get(pos)?);
+        }
+
+        Ok(elems)
+    }
+
+    /**
+     * Returns the positions in <left, right> that a chmin by val would change,
+     *  along with their current values, in O(log n) time per position.
+     */
+    fn changed_by_chmin(
+        &mut self,
+        left: usize,
+        right: usize,
+        val: i32,
+    ) -> Result<Vec<(usize, i32)>, Box<dyn Error>> {
+        let mut changed = Vec::new();
+        let mut start = left;
+        while start <= right {
+            match self.seg_tree.find_first(start, |max| max > val)? {
+                Some(pos) if pos <= right => {
+                    changed.push((pos, self.seg_tree.get(pos)?));
+                    start = pos + 1;
+                }
+                _ => break,
+            }
+        }
+
+        Ok(changed)
+    }
+
+    /**
+     * Converts the 1-based <l, r> range to a 0-based one of the tree's positions.
+     */
+    fn to_range(&self, l: i32, r: i32) -> Result<(usize, usize), Box<dyn Error>> {
+        let len = self.seg_tree.len();
+        if l < 1 || r < l || r as usize > len {
+            return Err(format!("<{}, {}> is not a valid range of <1, {}>.", l, r, len).into());
+        }
+
+        Ok((l as usize - 1, r as usize - 1))
+    }
+}
+
+fn parse(token: &str) -> Result<i32, Box<dyn Error>> {
+    token
+        .parse()
+        .map_err(|_| format!("'{}' is not an integer.", token).into())
+}
+
+fn parse_all(tokens: &[&str]) -> Result<Vec<i32>, Box<dyn Error>> {
+    tokens.iter().map(|token| parse(token)).collect()
+}
+
+/**
+ * Returns a line describing the node, indented by its depth, with 1-based positions.
+ */
+fn format_node(view: &NodeView) -> String {
+    format!(
+        "{}[{}, {}]{} min={} max={} sum={} pending={}",
+        "  ".repeat(view.depth),
+        view.start + 1,
+        view.end + 1,
+        view.describe_padding(),
+        view.data.min(),
+        view.data.max(),
+        view.data.sum(),
+        view.describe_pending()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /**
+     * Parses and runs line, returning its output or error message.
+     */
+    fn execute(session: &mut Session, line: &str) -> String {
+        match parse_command(line).and_then(|command| session.execute(command)) {
+            Ok(output) => output,
+            Err(why) => format!("Error: {}", why),
+        }
+    }
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(parse_command("  \n").unwrap(), Command::Empty);
+        assert_eq!(parse_command("exit").unwrap(), Command::Quit);
+        assert_eq!(
+            parse_command("load 3 -1 4").unwrap(),
+            Command::Load(vec![3, -1, 4])
+        );
+        assert_eq!(
+            parse_command("chmin 1 3 2").unwrap(),
+            Command::Chmin { l: 1, r: 3, val: 2 }
+        );
+        assert_eq!(
+            parse_command("max 2 2").unwrap(),
+            Command::Max { l: 2, r: 2 }
+        );
+
+        assert_eq!(
+            parse_command("chmin 1 x 2").unwrap_err().to_string(),
+            "'x' is not an integer."
+        );
+        assert_eq!(
+            parse_command("max 1").unwrap_err().to_string(),
+            "Unknown command or wrong arguments 'max', try 'help'."
+        );
+    }
+
+    #[test]
+    fn test_undo() {
+        let mut session = Session::new();
+        assert_eq!(execute(&mut session, "undo"), "Error: Nothing to undo.");
+
+        assert_eq!(
+            execute(&mut session, "load 5 1 9 3"),
+            "Loaded 4 elements.\n"
+        );
+        execute(&mut session, "chmin 1 4 4");
+        execute(&mut session, "chmin 2 3 0");
+        assert_eq!(session.elements().unwrap(), vec![4, 0, 0, 3]);
+        assert_eq!(execute(&mut session, "max 1 4"), "4\n");
+
+        // Only the elements the chmin changed are recorded
+        match session.history.back() {
+            Some(Inverse::Chmin(changed)) => assert_eq!(changed, &vec![(1, 1), (2, 4)]),
+            _ => panic!("expected the inverse of a chmin"),
+        }
+
+        execute(&mut session, "undo");
+        assert_eq!(session.elements().unwrap(), vec![4, 1, 4, 3]);
+        execute(&mut session, "undo");
+        assert_eq!(session.elements().unwrap(), vec![5, 1, 9, 3]);
+        execute(&mut session, "undo");
+        assert!(session.seg_tree.is_empty());
+
+        assert_eq!(
+            execute(&mut session, "max 1 1"),
+            "Error: <1, 1> is not a valid range of <1, 0>."
+        );
+    }
+
+    #[test]
+    fn test_dump() {
+        let mut session = Session::new();
+        execute(&mut session, "load 5 1 9");
+        execute(&mut session, "chmin 1 2 4");
+
+        assert_eq!(
+            execute(&mut session, "dump"),
+            "[1, 4] (1 padding) min=1 max=9 sum=14 pending=none
+  [1, 2] min=1 max=4 sum=5 pending=chmin 4
+    [1, 1] min=5 max=5 sum=5 pending=none
+    [2, 2] min=1 max=1 sum=1 pending=none
+  [3, 4] (1 padding) min=9 max=9 sum=9 pending=none
+    [3, 3] min=9 max=9 sum=9 pending=none
+"
+        );
+    }
+
+    #[test]
+    fn test_history_limit() {
+        let mut session = Session::new();
+        execute(&mut session, "load 1000");
+        for val in (0..HISTORY_LIMIT as i32).rev() {
+            execute(&mut session, &format!("chmin 1 1 {}", val));
+        }
+        assert_eq!(session.history.len(), HISTORY_LIMIT);
+
+        // The load was forgotten, the chmins are undone in reverse order
+        for _ in 0..HISTORY_LIMIT {
+            assert_eq!(execute(&mut session, "undo"), "");
+        }
+        assert_eq!(session.elements().unwrap(), vec![1000]);
+        assert_eq!(execute(&mut session, "undo"), "Error: Nothing to undo.");
+    }
+}
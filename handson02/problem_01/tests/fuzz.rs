@@ -0,0 +1,304 @@
+use problem_01::SegmentTree;
+use rand::Rng;
+use std::cmp;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+
+/**
+ * Query of the problem, with 0-based inclusive ranges.
+ */
+#[derive(Clone, Debug)]
+enum Op {
+    Chmin { start: usize, end: usize, val: i32 },
+    Max { start: usize, end: usize },
+}
+
+/**
+ * Formats the query as a line of the input file (1-based).
+ */
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Op::Chmin { start, end, val } => write!(f, "0 {} {} {}", start + 1, end + 1, val),
+            Op::Max { start, end } => write!(f, "1 {} {}", start + 1, end + 1),
+        }
+    }
+}
+
+impl Op {
+    fn range(&self) -> (usize, usize) {
+        match *self {
+            Op::Chmin { start, end, .. } | Op::Max { start, end } => (start, end),
+        }
+    }
+
+    fn with_range(&self, start: usize, end: usize) -> Op {
+        match *self {
+            Op::Chmin { val, .. } => Op::Chmin { start, end, val },
+            Op::Max { .. } => Op::Max { start, end },
+        }
+    }
+}
+
+/**
+ * Initial array and sequence of queries run on it.
+ */
+#[derive(Clone, Debug)]
+struct Script {
+    array: Vec<i32>,
+    ops: Vec<Op>,
+}
+
+/**
+ * Formats the script as an input file for the problem_01 binary.
+ */
+impl fmt::Display for Script {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} {}", self.array.len(), self.ops.len())?;
+        let elems: Vec<String> = self.array.iter().map(|x| x.to_string()).collect();
+        writeln!(f, "{}", elems.join(" "))?;
+        for op in &self.ops {
+            writeln!(f, "{}", op)?;
+        }
+
+        Ok(())
+    }
+}
+
+/**
+ * Generates a random script. Values are drawn from a small
+ *  interval so that ranges are full of repeated maximums.
+ */
+fn random_script<R: Rng>(rng: &mut R) -> Script {
+    let n = rng.gen_range(1..=40);
+    let array: Vec<i32> = (0..n).map(|_| rng.gen_range(-20..=20)).collect();
+
+    let ops = (0..rng.gen_range(1..=100))
+        .map(|_| {
+            let a = rng.gen_range(0..n);
+            let b = rng.gen_range(0..n);
+            let (start, end) = (cmp::min(a, b), cmp::max(a, b));
+            if rng.gen_bool(0.5) {
+                let val = rng.gen_range(-25..=25);
+                Op::Chmin { start, end, val }
+            } else {
+                Op::Max { start, end }
+            }
+        })
+        .collect();
+
+    Script { array, ops }
+}
+
+/**
+ * Runs the script on both the segment tree and a plain vector, returning
+ *  a description of the first point where they diverge, if any.
+ *  A panic in the tree counts as a divergence.
+ */
+fn check(script: &Script) -> Result<(), String> {
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let mut model = script.array.clone();
+        let mut seg_tree = SegmentTree::new(&script.array);
+
+        for (i, op) in script.ops.iter().enumerate() {
+            match *op {
+                Op::Chmin { start, end, val } => {
+                    seg_tree.chmin(start..=end, val).unwrap();
+                    for x in &mut model[start..=end] {
+                        *x = cmp::min(*x, val);
+                    }
+                }
+                Op::Max { start, end } => {
+                    let expected = *model[start..=end].iter().max().unwrap();
+                    let found = seg_tree.max(start..=end).unwrap();
+                    if found != expected {
+                        return Err(format!(
+                            "query {} ({}): expected {}, found {}",
+                            i + 1,
+                            op,
+                            expected,
+                            found
+                        ));
+                    }
+                }
+            }
+
+            if let Err(why) = seg_tree.validate() {
+                return Err(format!("after query {} ({}): {}", i + 1, op, why));
+            }
+        }
+
+        Ok(())
+    }));
+
+    match result {
+        Ok(outcome) => outcome,
+        Err(_) => Err(String::from("the segment tree panicked")),
+    }
+}
+
+/**
+ * Returns scripts derived from script by removing queries, removing
+ *  elements or moving values towards 0, roughly from the largest change
+ *  to the smallest. All of them are valid scripts.
+ */
+fn simplifications(script: &Script) -> Vec<Script> {
+    let mut out = Vec::new();
+
+    // Remove chunks of queries, halving the chunk size each round
+    let mut chunk = script.ops.len();
+    while chunk > 0 {
+        for start in (0..script.ops.len()).step_by(chunk) {
+            let mut ops = script.ops.clone();
+            ops.drain(start..cmp::min(start + chunk, ops.len()));
+            out.push(Script {
+                array: script.array.clone(),
+                ops,
+            });
+        }
+        chunk /= 2;
+    }
+
+    // Remove an element, shrinking the ranges that contain it
+    // and dropping the queries on it alone
+    if script.array.len() > 1 {
+        for pos in 0..script.array.len() {
+            let mut array = script.array.clone();
+            array.remove(pos);
+            let ops = script
+                .ops
+                .iter()
+                .filter(|op| op.range() != (pos, pos))
+                .map(|op| {
+                    let (start, end) = op.range();
+                    let start = if start > pos { start - 1 } else { start };
+                    let end = if end >= pos { end - 1 } else { end };
+                    op.with_range(start, end)
+                })
+                .collect();
+            out.push(Script { array, ops });
+        }
+    }
+
+    // Narrow the ranges of the queries
+    for (i, op) in script.ops.iter().enumerate() {
+        let (start, end) = op.range();
+        if start == end {
+            continue;
+        }
+        for (new_start, new_end) in [(start + 1, end), (start, end - 1)] {
+            let mut ops = script.ops.clone();
+            ops[i] = op.with_range(new_start, new_end);
+            out.push(Script {
+                array: script.array.clone(),
+                ops,
+            });
+        }
+    }
+
+    // Move values towards 0
+    let smaller = |x: i32| {
+        [0, x / 2, x - x.signum()]
+            .into_iter()
+            .filter(move |&y| y != x)
+    };
+    for (pos, &x) in script.array.iter().enumerate() {
+        for y in smaller(x) {
+            let mut array = script.array.clone();
+            array[pos] = y;
+            out.push(Script {
+                array,
+                ops: script.ops.clone(),
+            });
+        }
+    }
+    for (i, op) in script.ops.iter().enumerate() {
+        if let Op::Chmin { start, end, val } = *op {
+            for y in smaller(val) {
+                let mut ops = script.ops.clone();
+                ops[i] = Op::Chmin { start, end, val: y };
+                out.push(Script {
+                    array: script.array.clone(),
+                    ops,
+                });
+            }
+        }
+    }
+
+    out
+}
+
+/**
+ * Greedily replaces script with its first simplification that still fails,
+ *  until none does. The result fails but none of its simplifications does.
+ */
+fn shrink<F: Fn(&Script) -> bool>(mut script: Script, fails: F) -> Script {
+    while let Some(smaller) = simplifications(&script)
+        .into_iter()
+        .find(|candidate| fails(candidate))
+    {
+        script = smaller;
+    }
+
+    script
+}
+
+#[test]
+fn test_against_model() {
+    let mut rng = rand::thread_rng();
+
+    for _ in 0..2000 {
+        let script = random_script(&mut rng);
+        if check(&script).is_err() {
+            // Keep the tree's panics from flooding the output while shrinking
+            panic::set_hook(Box::new(|_| {}));
+            let minimal = shrink(script, |s| check(s).is_err());
+            let _ = panic::take_hook();
+
+            panic!(
+                "Mismatch, {}. Minimal failing input:\n{}",
+                check(&minimal).unwrap_err(),
+                minimal
+            );
+        }
+    }
+}
+
+#[test]
+fn test_shrink() {
+    // Stand-in for a bug: any max query answering at least 10
+    let fails = |script: &Script| {
+        let mut model = script.array.clone();
+        script.ops.iter().any(|op| match *op {
+            Op::Chmin { start, end, val } => {
+                for x in &mut model[start..=end] {
+                    *x = cmp::min(*x, val);
+                }
+                false
+            }
+            Op::Max { start, end } => model[start..=end].iter().max() >= Some(&10),
+        })
+    };
+
+    let script = Script {
+        array: vec![3, 15, -4, 12, 7, 0],
+        ops: vec![
+            Op::Chmin {
+                start: 0,
+                end: 2,
+                val: 11,
+            },
+            Op::Max { start: 1, end: 4 },
+            Op::Chmin {
+                start: 3,
+                end: 5,
+                val: 2,
+            },
+            Op::Max { start: 0, end: 5 },
+        ],
+    };
+    assert!(fails(&script));
+
+    let minimal = shrink(script, fails);
+    assert_eq!(minimal.to_string(), "1 1\n10\n1 1 1\n");
+}
@@ -0,0 +1,93 @@
+use bench_timer::measure_elapsed_time;
+use problem_02::batch::{self, Operation, Query};
+use problem_02::fenwick::FenwickTree;
+use problem_02::SegmentTree;
+use rand::Rng;
+
+const N_RUNS: usize = 3;
+
+/**
+ * Compares the backends of the problem_02 binary on random inputs of
+ *  growing size (n = m = k) and prints the results as csv.
+ */
+fn main() {
+    let mut rng = rand::thread_rng();
+
+    println!("n,SegmentTree,Fenwick,DifferenceArray");
+    for size in [1_000, 10_000, 100_000, 1_000_000] {
+        let (elems, ops, queries) = generate_input(&mut rng, size);
+
+        // Counting the operations' applications is common to every backend
+        let (segment_tree_elapsed, segment_tree_results) = measure_elapsed_time(N_RUNS, || {
+            let counts = batch::operation_counts(ops.len(), &queries).unwrap();
+            let mut seg_tree = SegmentTree::new(&elems);
+            for (op, count) in ops.iter().zip(counts) {
+                seg_tree.add(op.start..=op.end, op.val * count).unwrap();
+            }
+            seg_tree.leaves()
+        });
+
+        let (fenwick_elapsed, fenwick_results) = measure_elapsed_time(N_RUNS, || {
+            let counts = batch::operation_counts(ops.len(), &queries).unwrap();
+            let mut fenwick = FenwickTree::new(&elems);
+            for (op, count) in ops.iter().zip(counts) {
+                fenwick.add(op.start..=op.end, op.val * count).unwrap();
+            }
+            fenwick.leaves()
+        });
+
+        let (diff_array_elapsed, diff_array_results) = measure_elapsed_time(N_RUNS, || {
+            let counts = batch::operation_counts(ops.len(), &queries).unwrap();
+            batch::apply_with_difference_array(&elems, &ops, &counts).unwrap()
+        });
+
+        assert_eq!(
+            segment_tree_results, fenwick_results,
+            "Mismatch on n = {}",
+            size
+        );
+        assert_eq!(
+            segment_tree_results, diff_array_results,
+            "Mismatch on n = {}",
+            size
+        );
+
+        println!(
+            "{},{},{},{}",
+            size, segment_tree_elapsed, fenwick_elapsed, diff_array_elapsed
+        );
+    }
+}
+
+/**
+ * Returns an array of size elements, along with size operations
+ *  and size queries on it.
+ */
+fn generate_input(rng: &mut impl Rng, size: usize) -> (Vec<i64>, Vec<Operation>, Vec<Query>) {
+    let elems = (0..size).map(|_| rng.gen_range(0..1_000_000)).collect();
+
+    let mut random_range = || {
+        let a = rng.gen_range(0..size);
+        let b = rng.gen_range(0..size);
+        (a.min(b), a.max(b))
+    };
+
+    let ops = (0..size)
+        .map(|_| {
+            let (start, end) = random_range();
+            Operation {
+                start,
+                end,
+                val: (start % 1000) as i64,
+            }
+        })
+        .collect();
+    let queries = (0..size)
+        .map(|_| {
+            let (start, end) = random_range();
+            Query { start, end }
+        })
+        .collect();
+
+    (elems, ops, queries)
+}
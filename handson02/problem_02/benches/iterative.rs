@@ -0,0 +1,109 @@
+use std::fs;
+use std::path::Path;
+
+use bench_timer::measure_elapsed_time;
+use problem_02::batch::{self, Query};
+use problem_02::fenwick::FenwickTree;
+use problem_02::iterative::IterativeSegmentTree;
+use problem_02::SegmentTree;
+
+const N_RUNS: usize = 5;
+
+/**
+ * Compares the recursive and the iterative segment trees, along with the
+ *  Fenwick tree, on the tests/input*.txt workloads and prints the results as csv.
+ */
+fn main() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests");
+
+    println!("Input,n,updates,Recursive,Iterative,Fenwick");
+    for i in 0.. {
+        let path = dir.join(format!("input{}.txt", i));
+        let Ok(text) = fs::read_to_string(&path) else {
+            break;
+        };
+        let (elems, updates) = parse_input(&text);
+
+        let (recursive_elapsed, recursive_results) = measure_elapsed_time(N_RUNS, || {
+            let mut seg_tree = SegmentTree::new(&elems);
+            for &(start, end, val) in &updates {
+                seg_tree.add(start..=end, val).unwrap();
+            }
+            seg_tree.leaves()
+        });
+
+        let (iterative_elapsed, iterative_results) = measure_elapsed_time(N_RUNS, || {
+            let mut seg_tree = IterativeSegmentTree::new(&elems);
+            for &(start, end, val) in &updates {
+                seg_tree.add(start..=end, val).unwrap();
+            }
+            seg_tree.leaves()
+        });
+
+        let (fenwick_elapsed, fenwick_results) = measure_elapsed_time(N_RUNS, || {
+            let mut fenwick = FenwickTree::new(&elems);
+            for &(start, end, val) in &updates {
+                fenwick.add(start..=end, val).unwrap();
+            }
+            fenwick.leaves()
+        });
+
+        assert_eq!(
+            recursive_results, iterative_results,
+            "Mismatch on input{}",
+            i
+        );
+        assert_eq!(recursive_results, fenwick_results, "Mismatch on input{}", i);
+
+        println!(
+            "input{},{},{},{},{},{}",
+            i,
+            elems.len(),
+            updates.len(),
+            recursive_elapsed,
+            iterative_elapsed,
+            fenwick_elapsed
+        );
+    }
+}
+
+/**
+ * Parses the input and returns the array along with the range additions
+ *  (0-based, inclusive) to apply to it, each operation's value already
+ *  multiplied by the number of queries including it.
+ */
+fn parse_input(text: &str) -> (Vec<i64>, Vec<(usize, usize, i64)>) {
+    let mut lines = text.lines();
+    let mut next_numbers = || -> Vec<i64> {
+        lines
+            .next()
+            .expect("Unexpected end of input.")
+            .split_whitespace()
+            .map(|x| x.parse().expect("Not an integer!"))
+            .collect()
+    };
+
+    let header = next_numbers();
+    let (m, k) = (header[1] as usize, header[2] as usize);
+    let elems = next_numbers();
+    let ops: Vec<Vec<i64>> = (0..m).map(|_| next_numbers()).collect();
+
+    let queries: Vec<Query> = (0..k)
+        .map(|_| {
+            let query = next_numbers();
+            Query {
+                start: query[0] as usize - 1,
+                end: query[1] as usize - 1,
+            }
+        })
+        .collect();
+
+    let counts = batch::operation_counts(m, &queries).expect("Invalid query.");
+    let updates = ops
+        .iter()
+        .zip(counts)
+        .map(|(op, count)| ((op[0] - 1) as usize, (op[1] - 1) as usize, op[2] * count))
+        .collect();
+
+    (elems, updates)
+}
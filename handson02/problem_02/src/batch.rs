@@ -0,0 +1,381 @@
+use std::error::Error;
+use std::fmt;
+
+use segment_tree::to_bounds;
+
+use crate::{SegmentTree, SegmentTreeError};
+
+/**
+ * Increase of the elements in the <start, end> (0-based, inclusive) range by val.
+ */
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Operation {
+    pub start: usize,
+    pub end: usize,
+    pub val: i64,
+}
+
+/**
+ * Application of the operations in the <start, end> (0-based, inclusive) range.
+ */
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Query {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BatchError {
+    // Operation whose range is not a valid range of the array
+    InvalidOperation {
+        index: usize,
+        error: SegmentTreeError,
+    },
+    // Query whose range is not a valid range of the operations
+    InvalidQuery {
+        index: usize,
+        len: usize,
+    },
+    // Operation that makes an element overflow, applied count times
+    Overflow {
+        operation: usize,
+        count: i64,
+    },
+    // Number of application counts that doesn't match the number of operations
+    CountsMismatch {
+        counts: usize,
+        ops: usize,
+    },
+}
+
+impl fmt::Display for BatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BatchError::InvalidOperation { index, error } => {
+                write!(f, "operation {}: {}", index, error)
+            }
+            BatchError::InvalidQuery { index, len } => {
+                write!(
+                    f,
+                    "query {} is not a valid range of {} operations",
+                    index, len
+                )
+            }
+            BatchError::Overflow { operation, count } => {
+                write!(
+                    f,
+                    "operation {}, applied {} times, makes an element overflow an i64",
+                    operation, count
+                )
+            }
+            BatchError::CountsMismatch { counts, ops } => {
+                write!(f, "{} application counts for {} operations", counts, ops)
+            }
+        }
+    }
+}
+
+impl Error for BatchError {}
+
+/**
+ * Returns the number of times each of the ops_len operations is applied by queries.
+ */
+pub fn operation_counts(ops_len: usize, queries: &[Query]) -> Result<Vec<i64>, BatchError> {
+    // Difference array initialized to 0
+    let mut ops_diff_array: Vec<i64> = vec![0; ops_len + 1];
+    for (index, query) in queries.iter().enumerate() {
+        if query.start > query.end || query.end >= ops_len {
+            return Err(BatchError::InvalidQuery {
+                index,
+                len: ops_len,
+            });
+        }
+
+        ops_diff_array[query.start] += 1;
+        ops_diff_array[query.end + 1] -= 1;
+    }
+
+    // The ith count is the prefix sum of the difference array up to i
+    let mut count = 0;
+    Ok(ops_diff_array[..ops_len]
+        .iter()
+        .map(|diff| {
+            count += diff;
+            count
+        })
+        .collect())
+}
+
+/**
+ * Applies to array every operation in ops, as many times as it is
+ *  included by queries, and returns the resulting array.
+ *
+ * See apply_with_segment_tree, the whole batch takes O((m + k) + m log n) time.
+ */
+pub fn apply_query_batches(
+    array: &[i64],
+    ops: &[Operation],
+    queries: &[Query],
+) -> Result<Vec<i64>, BatchError> {
+    let counts = operation_counts(ops.len(), queries)?;
+
+    apply_with_segment_tree(array, ops, &counts)
+}
+
+/**
+ * Applies to array every operation in ops, the ith one counts[i] times,
+ *  and returns the resulting array.
+ *
+ * Each operation is applied just once, with its value multiplied by
+ *  the number of times it's included, on a segment tree in checked mode,
+ *  so the whole batch takes O(m log n) time and fails instead of
+ *  returning wrapped around values.
+ */
+pub fn apply_with_segment_tree(
+    array: &[i64],
+    ops: &[Operation],
+    counts: &[i64],
+) -> Result<Vec<i64>, BatchError> {
+    check_counts(ops, counts)?;
+
+    let mut seg_tree = SegmentTree::new(array);
+    for (index, (op, &count)) in ops.iter().zip(counts).enumerate() {
+        check_order(index, op)?;
+
+        let val = op.val.checked_mul(count).ok_or(BatchError::Overflow {
+            operation: index,
+            count,
+        })?;
+
+        seg_tree
+            .checked_add(op.start..=op.end, val)
+            .map_err(|error| match error {
+                SegmentTreeError::Overflow => BatchError::Overflow {
+                    operation: index,
+                    count,
+                },
+                error => BatchError::InvalidOperation { index, error },
+            })?;
+    }
+
+    Ok(seg_tree.leaves())
+}
+
+/**
+ * Returns an error if counts doesn't hold exactly one count per operation.
+ */
+fn check_counts(ops: &[Operation], counts: &[i64]) -> Result<(), BatchError> {
+    if counts.len() != ops.len() {
+        return Err(BatchError::CountsMismatch {
+            counts: counts.len(),
+            ops: ops.len(),
+        });
+    }
+
+    Ok(())
+}
+
+/**
+ * Returns an error if op, the index-th operation, ends before it starts.
+ *  Such a range would otherwise be taken for an empty one and the
+ *  operation silently skipped.
+ */
+fn check_order(index: usize, op: &Operation) -> Result<(), BatchError> {
+    if op.start > op.end {
+        return Err(BatchError::InvalidOperation {
+            index,
+            error: SegmentTreeError::ReversedRange {
+                start: op.start,
+                end: op.end,
+            },
+        });
+    }
+
+    Ok(())
+}
+
+/**
+ * Applies to array every operation in ops, the ith one counts[i] times,
+ *  and returns the resulting array.
+ *
+ * Since no element is read before all the operations are applied, a
+ *  difference array over the elements is enough: no tree is needed and
+ *  the whole batch takes O(n + m) time. Elements that overflow wrap around.
+ */
+pub fn apply_with_difference_array(
+    array: &[i64],
+    ops: &[Operation],
+    counts: &[i64],
+) -> Result<Vec<i64>, BatchError> {
+    check_counts(ops, counts)?;
+
+    // Difference array initialized to 0
+    let mut diff_array: Vec<i64> = vec![0; array.len() + 1];
+    for (index, (op, &count)) in ops.iter().zip(counts).enumerate() {
+        check_order(index, op)?;
+        let (start, end) = to_bounds(op.start..=op.end, array.len()).map_err(|error| {
+            BatchError::InvalidOperation {
+                index,
+                error: error.into(),
+            }
+        })?;
+
+        let val = op.val.wrapping_mul(count);
+        diff_array[start] = diff_array[start].wrapping_add(val);
+        diff_array[end] = diff_array[end].wrapping_sub(val);
+    }
+
+    // The ith element is increased by the prefix sum of the difference array up to i
+    let mut increase: i64 = 0;
+    Ok(array
+        .iter()
+        .zip(diff_array)
+        .map(|(x, diff)| {
+            increase = increase.wrapping_add(diff);
+            x.wrapping_add(increase)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_query_batches() {
+        let array = vec![1, 2, 3];
+        let ops = [
+            Operation {
+                start: 0,
+                end: 1,
+                val: 1,
+            },
+            Operation {
+                start: 0,
+                end: 2,
+                val: 2,
+            },
+            Operation {
+                start: 1,
+                end: 2,
+                val: 3,
+            },
+        ];
+        let queries = [
+            Query { start: 0, end: 1 },
+            Query { start: 0, end: 2 },
+            Query { start: 1, end: 2 },
+        ];
+
+        let counts = operation_counts(3, &queries).unwrap();
+        assert_eq!(counts, vec![2, 3, 2]);
+        assert_eq!(
+            apply_query_batches(&array, &ops, &queries),
+            Ok(vec![9, 16, 15])
+        );
+        assert_eq!(
+            apply_with_difference_array(&array, &ops, &counts),
+            Ok(vec![9, 16, 15])
+        );
+    }
+
+    #[test]
+    fn test_validation() {
+        let ops = [Operation {
+            start: 1,
+            end: 3,
+            val: i64::MAX,
+        }];
+
+        assert_eq!(
+            apply_query_batches(&[0; 3], &ops, &[Query { start: 0, end: 1 }]),
+            Err(BatchError::InvalidQuery { index: 0, len: 1 })
+        );
+        assert_eq!(
+            apply_with_difference_array(&[0; 3], &ops, &[0]),
+            Err(BatchError::InvalidOperation {
+                index: 0,
+                error: SegmentTreeError::OutOfBounds { index: 3, len: 3 }
+            })
+        );
+        assert_eq!(
+            apply_query_batches(&[0; 3], &ops, &[]),
+            Err(BatchError::InvalidOperation {
+                index: 0,
+                error: SegmentTreeError::OutOfBounds { index: 3, len: 3 }
+            })
+        );
+        assert_eq!(
+            apply_query_batches(&[0; 4], &ops, &[Query { start: 0, end: 0 }; 2]),
+            Err(BatchError::Overflow {
+                operation: 0,
+                count: 2
+            })
+        );
+        assert_eq!(
+            apply_query_batches(&[0, -1, 0, 0], &ops, &[Query { start: 0, end: 0 }]),
+            Ok(vec![0, i64::MAX - 1, i64::MAX, i64::MAX])
+        );
+    }
+
+    #[test]
+    fn test_reversed_operation() {
+        let ops = [
+            Operation {
+                start: 0,
+                end: 0,
+                val: 1,
+            },
+            Operation {
+                start: 2,
+                end: 1,
+                val: 5,
+            },
+        ];
+
+        assert_eq!(
+            apply_query_batches(&[0; 3], &ops, &[Query { start: 0, end: 1 }]),
+            Err(BatchError::InvalidOperation {
+                index: 1,
+                error: SegmentTreeError::ReversedRange { start: 2, end: 1 }
+            })
+        );
+        assert_eq!(
+            apply_with_difference_array(&[0; 3], &ops, &[1, 1]),
+            Err(BatchError::InvalidOperation {
+                index: 1,
+                error: SegmentTreeError::ReversedRange { start: 2, end: 1 }
+            })
+        );
+    }
+
+    #[test]
+    fn test_counts_mismatch() {
+        let ops = [Operation {
+            start: 0,
+            end: 1,
+            val: 1,
+        }; 2];
+
+        assert_eq!(
+            apply_with_difference_array(&[0; 3], &ops, &[1]),
+            Err(BatchError::CountsMismatch { counts: 1, ops: 2 })
+        );
+        assert_eq!(
+            apply_with_difference_array(&[0; 3], &ops, &[1, 1, 1]),
+            Err(BatchError::CountsMismatch { counts: 3, ops: 2 })
+        );
+        assert_eq!(
+            apply_with_segment_tree(&[0; 3], &ops, &[1]),
+            Err(BatchError::CountsMismatch { counts: 1, ops: 2 })
+        );
+        assert_eq!(
+            apply_with_difference_array(&[0; 3], &ops, &[1, 2]),
+            Ok(vec![3, 3, 0])
+        );
+        assert_eq!(
+            apply_with_segment_tree(&[0; 3], &ops, &[1, 2]),
+            Ok(vec![3, 3, 0])
+        );
+    }
+}
@@ -0,0 +1,268 @@
+use std::cmp;
+use std::ops::RangeBounds;
+
+use segment_tree::to_bounds;
+
+use crate::SegmentTreeError;
+
+/**
+ * Fenwick tree (binary indexed tree) supporting range add updates,
+ *  point queries and range sum queries in O(log n) time.
+ *
+ * Two BITs are kept over the difference array d of the elements:
+ *  b1 stores d[i] and b2 stores d[i] * i, so that the prefix sum
+ *  of the first p elements is p * sum(b1, p) - sum(b2, p).
+ * Values are accumulated as i128, since the intermediate products
+ *  can overflow an i64 even when the final sums don't.
+ *
+ * Elements are read back wrapped around like i64 arithmetic, as in
+ *  SegmentTree::add, while sums are computed on the exact elements.
+ *  The two only differ once an element has overflowed, which a BIT
+ *  can't tell, so sum reports an Overflow as soon as bound says that
+ *  any element might have, instead of a possibly different sum.
+ */
+#[derive(Debug)]
+pub struct FenwickTree {
+    b1: Vec<i128>,
+    b2: Vec<i128>,
+    bound: i128, // Upper bound on the absolute value of the exact elements
+}
+
+impl FenwickTree {
+    /**
+     * Builds a Fenwick tree on the provided data and returns it.
+     */
+    pub fn new(data: &[i64]) -> Self {
+        let n = data.len();
+
+        // Build both trees in O(n) by pushing each node's value to its parent
+        let mut b1 = vec![0; n + 1];
+        let mut b2 = vec![0; n + 1];
+        let mut prev = 0;
+        let mut bound = 0;
+        for (i, &x) in data.iter().enumerate() {
+            bound = cmp::max(bound, (x as i128).abs());
+            let diff = x as i128 - prev;
+            b1[i + 1] = diff;
+            b2[i + 1] = diff * i as i128;
+            prev = x as i128;
+        }
+        for i in 1..=n {
+            let parent = i + lowest_bit(i);
+            if parent <= n {
+                b1[parent] += b1[i];
+                b2[parent] += b2[i];
+            }
+        }
+
+        Self { b1, b2, bound }
+    }
+
+    /**
+     * Returns the number of elements in the tree.
+     */
+    pub fn len(&self) -> usize {
+        self.b1.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /**
+     * Increases the elements in range by val.
+     *  Elements that overflow wrap around when read, see get.
+     */
+    pub fn add<R: RangeBounds<usize>>(
+        &mut self,
+        range: R,
+        val: i64,
+    ) -> Result<(), SegmentTreeError> {
+        let (start, end) = to_bounds(range, self.len())?;
+        if start == end {
+            return Ok(());
+        }
+
+        self.bound = self.bound.saturating_add((val as i128).abs());
+        self.diff_add(start, val as i128);
+        self.diff_add(end, -(val as i128));
+
+        Ok(())
+    }
+
+    /**
+     * Returns the element at position pos, wrapped around if it overflowed.
+     */
+    pub fn get(&self, pos: usize) -> Result<i64, SegmentTreeError> {
+        if pos >= self.len() {
+            return Err(SegmentTreeError::OutOfBounds {
+                index: pos,
+                len: self.len(),
+            });
+        }
+
+        // The element is the prefix sum of the difference array
+        Ok(wrap(prefix_sum(&self.b1, pos + 1)))
+    }
+
+    /**
+     * Returns the sum of the elements in range, or an Overflow error
+     *  if it doesn't fit in an i64 or some element might have wrapped around.
+     */
+    pub fn sum<R: RangeBounds<usize>>(&self, range: R) -> Result<i64, SegmentTreeError> {
+        let (start, end) = to_bounds(range, self.len())?;
+        if start == end {
+            return Err(SegmentTreeError::EmptyRange);
+        }
+        if self.bound > i64::MAX as i128 {
+            return Err(SegmentTreeError::Overflow);
+        }
+
+        i64::try_from(self.prefix_sum(end) - self.prefix_sum(start))
+            .map_err(|_| SegmentTreeError::Overflow)
+    }
+
+    /**
+     * Returns all of the array's elements, wrapped around if they overflowed.
+     */
+    pub fn leaves(&self) -> Vec<i64> {
+        // Recover the difference array in O(n), then accumulate it
+        let mut diffs = self.b1.clone();
+        for i in (1..diffs.len()).rev() {
+            let parent = i + lowest_bit(i);
+            if parent < diffs.len() {
+                diffs[parent] -= diffs[i];
+            }
+        }
+
+        let mut val = 0;
+        diffs[1..]
+            .iter()
+            .map(|diff| {
+                val += diff;
+                wrap(val)
+            })
+            .collect()
+    }
+
+    /**
+     * Adds val to position pos (0-based) of the difference array.
+     */
+    fn diff_add(&mut self, pos: usize, val: i128) {
+        let mut i = pos + 1;
+        while i < self.b1.len() {
+            self.b1[i] += val;
+            self.b2[i] += val * pos as i128;
+            i += lowest_bit(i);
+        }
+    }
+
+    /**
+     * Returns the sum of the first p elements.
+     */
+    fn prefix_sum(&self, p: usize) -> i128 {
+        p as i128 * prefix_sum(&self.b1, p) - prefix_sum(&self.b2, p)
+    }
+}
+
+/**
+ * Returns the sum of the first p values stored in the BIT b.
+ */
+fn prefix_sum(b: &[i128], mut p: usize) -> i128 {
+    let mut sum = 0;
+    while p > 0 {
+        sum += b[p];
+        p -= lowest_bit(p);
+    }
+
+    sum
+}
+
+/**
+ * Returns the i64 that val wraps around to, the same value
+ *  wrapping i64 additions summing up to val would give.
+ */
+fn wrap(val: i128) -> i64 {
+    val.rem_euclid(1 << 64) as u64 as i64
+}
+
+fn lowest_bit(i: usize) -> usize {
+    i & i.wrapping_neg()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SegmentTree;
+    use rand::Rng;
+
+    #[test]
+    fn test_against_recursive() {
+        let mut rng = rand::thread_rng();
+
+        for n in [1, 2, 7, 100, 129] {
+            let data: Vec<i64> = (0..n).map(|_| rng.gen_range(-1000..1000)).collect();
+            let mut fenwick = FenwickTree::new(&data);
+            let mut recursive = SegmentTree::new(&data);
+
+            for _ in 0..1000 {
+                let a = rng.gen_range(0..n);
+                let b = rng.gen_range(0..n);
+                let (start, end) = (cmp::min(a, b), cmp::max(a, b));
+
+                match rng.gen_range(0..3) {
+                    0 => {
+                        let val = rng.gen_range(-1000..1000);
+                        fenwick.add(start..=end, val).unwrap();
+                        recursive.add(start..=end, val).unwrap();
+                        assert_eq!(recursive.validate(), Ok(()));
+                    }
+                    1 => assert_eq!(fenwick.get(start), recursive.get(start)),
+                    _ => assert_eq!(fenwick.sum(start..=end), recursive.sum(start..=end)),
+                }
+            }
+
+            assert_eq!(fenwick.leaves(), recursive.leaves());
+        }
+    }
+
+    #[test]
+    fn test_wrapping_add() {
+        let mut rng = rand::thread_rng();
+
+        let data: Vec<i64> = (0..37).map(|_| rng.gen()).collect();
+        let mut fenwick = FenwickTree::new(&data);
+        let mut recursive = SegmentTree::new(&data);
+        for _ in 0..500 {
+            let a = rng.gen_range(0..data.len());
+            let b = rng.gen_range(0..data.len());
+            let (start, end) = (cmp::min(a, b), cmp::max(a, b));
+
+            let val = rng.gen();
+            fenwick.add(start..=end, val).unwrap();
+            recursive.add(start..=end, val).unwrap();
+            assert_eq!(fenwick.get(end), recursive.get(end));
+        }
+
+        assert_eq!(fenwick.leaves(), recursive.leaves());
+
+        // Sums are refused once an element might have wrapped around
+        let mut fenwick = FenwickTree::new(&[i64::MAX - 1, 0]);
+        assert_eq!(fenwick.sum(..), Ok(i64::MAX - 1));
+        fenwick.add(1..2, 2).unwrap();
+        assert_eq!(fenwick.sum(..), Err(SegmentTreeError::Overflow));
+        fenwick.add(0..1, 2).unwrap();
+        assert_eq!(fenwick.leaves(), vec![i64::MIN, 2]);
+    }
+
+    #[test]
+    fn test_range_errors() {
+        let mut fenwick = FenwickTree::new(&[1, 2, 3]);
+        assert_eq!(
+            fenwick.add(1..=3, 1),
+            Err(SegmentTreeError::OutOfBounds { index: 3, len: 3 })
+        );
+        assert_eq!(fenwick.sum(2..2), Err(SegmentTreeError::EmptyRange));
+        assert!(FenwickTree::new(&[]).leaves().is_empty());
+    }
+}
@@ -0,0 +1,170 @@
+use std::ops::RangeBounds;
+
+use segment_tree::to_bounds;
+
+use crate::SegmentTreeError;
+
+/**
+ * Non-recursive segment tree supporting range add updates
+ *  and point queries in O(log n) time.
+ *
+ * Nodes are laid out implicitly in a 2n array: leaves are stored in
+ *  t[n..2n] and node i's children are 2i and 2i + 1, so no padding
+ *  nor ranges need to be stored.
+ * Internal nodes only hold the value added to their whole range.
+ *
+ * Elements wrap around like i64 arithmetic, as in SegmentTree::add:
+ *  since wrapping additions commute, adding val to an ancestor and
+ *  summing the ancestors later gives the same wrapped element.
+ */
+#[derive(Debug)]
+pub struct IterativeSegmentTree {
+    n: usize,
+    t: Vec<i64>,
+}
+
+impl IterativeSegmentTree {
+    /**
+     * Builds a segment tree on the provided data and returns it.
+     */
+    pub fn new(data: &[i64]) -> Self {
+        let n = data.len();
+
+        let mut t = vec![0; 2 * n];
+        t[n..].copy_from_slice(data);
+
+        Self { n, t }
+    }
+
+    /**
+     * Returns the number of elements in the tree.
+     */
+    pub fn len(&self) -> usize {
+        self.n
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+
+    /**
+     * Increases the elements in range by val.
+     *  Elements that overflow wrap around.
+     */
+    pub fn add<R: RangeBounds<usize>>(
+        &mut self,
+        range: R,
+        val: i64,
+    ) -> Result<(), SegmentTreeError> {
+        let (start, end) = to_bounds(range, self.n)?;
+
+        let (mut l, mut r) = (start + self.n, end + self.n);
+        while l < r {
+            if l & 1 == 1 {
+                self.t[l] = self.t[l].wrapping_add(val);
+                l += 1;
+            }
+            if r & 1 == 1 {
+                r -= 1;
+                self.t[r] = self.t[r].wrapping_add(val);
+            }
+            l >>= 1;
+            r >>= 1;
+        }
+
+        Ok(())
+    }
+
+    /**
+     * Returns the element at position pos.
+     */
+    pub fn get(&self, pos: usize) -> Result<i64, SegmentTreeError> {
+        if pos >= self.n {
+            return Err(SegmentTreeError::OutOfBounds {
+                index: pos,
+                len: self.n,
+            });
+        }
+
+        // The element is the sum of the values added to all of its ancestors
+        let mut val: i64 = 0;
+        let mut p = pos + self.n;
+        while p > 0 {
+            val = val.wrapping_add(self.t[p]);
+            p >>= 1;
+        }
+
+        Ok(val)
+    }
+
+    /**
+     * Returns all of the array's elements, pushing every addition down to the leaves.
+     */
+    pub fn leaves(&mut self) -> Vec<i64> {
+        for i in 1..self.n {
+            self.t[2 * i] = self.t[2 * i].wrapping_add(self.t[i]);
+            self.t[2 * i + 1] = self.t[2 * i + 1].wrapping_add(self.t[i]);
+            self.t[i] = 0;
+        }
+
+        self.t[self.n..].to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SegmentTree;
+    use rand::Rng;
+    use std::cmp;
+
+    #[test]
+    fn test_against_recursive() {
+        let mut rng = rand::thread_rng();
+
+        // Lengths that are not a power of 2 are the interesting ones
+        for n in [1, 2, 7, 100, 129] {
+            let data: Vec<i64> = (0..n).map(|_| rng.gen_range(-1000..1000)).collect();
+            let mut iterative = IterativeSegmentTree::new(&data);
+            let mut recursive = SegmentTree::new(&data);
+
+            for _ in 0..1000 {
+                let a = rng.gen_range(0..n);
+                let b = rng.gen_range(0..n);
+                let (start, end) = (cmp::min(a, b), cmp::max(a, b));
+
+                if rng.gen_bool(0.5) {
+                    let val = rng.gen_range(-1000..1000);
+                    iterative.add(start..=end, val).unwrap();
+                    recursive.add(start..=end, val).unwrap();
+                    assert_eq!(recursive.validate(), Ok(()));
+                } else {
+                    assert_eq!(iterative.get(start), recursive.get(start));
+                }
+            }
+
+            assert_eq!(iterative.leaves(), recursive.leaves());
+        }
+    }
+
+    #[test]
+    fn test_wrapping_add() {
+        let mut rng = rand::thread_rng();
+
+        let data: Vec<i64> = (0..37).map(|_| rng.gen()).collect();
+        let mut iterative = IterativeSegmentTree::new(&data);
+        let mut recursive = SegmentTree::new(&data);
+        for _ in 0..500 {
+            let a = rng.gen_range(0..data.len());
+            let b = rng.gen_range(0..data.len());
+            let (start, end) = (cmp::min(a, b), cmp::max(a, b));
+
+            let val = rng.gen();
+            iterative.add(start..=end, val).unwrap();
+            recursive.add(start..=end, val).unwrap();
+            assert_eq!(iterative.get(end), recursive.get(end));
+        }
+
+        assert_eq!(iterative.leaves(), recursive.leaves());
+    }
+}
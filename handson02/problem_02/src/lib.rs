@@ -1,179 +1,382 @@
-#[derive(Debug)]
-pub struct SegmentTree {
-    nodes: Vec<Node>,
+use std::error::Error;
+use std::fmt;
+use std::ops::RangeBounds;
+
+use segment_tree::{to_bounds, Add, RangeAddTree, RangeError, Sum};
+
+pub mod batch;
+pub mod fenwick;
+pub mod iterative;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SegmentTreeError {
+    // Position past the end of the array
+    OutOfBounds { index: usize, len: usize },
+    // Range whose start comes after its (exclusive) end
+    ReversedRange { start: usize, end: usize },
+    // Query on a range with no elements
+    EmptyRange,
+    // Element or sum that doesn't fit in an i64
+    Overflow,
 }
 
-#[derive(Copy, Clone, Debug)]
-pub struct Data {
-    val: i32,
+impl fmt::Display for SegmentTreeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SegmentTreeError::OutOfBounds { index, len } => {
+                write!(f, "index {} is out of bounds for length {}", index, len)
+            }
+            SegmentTreeError::ReversedRange { start, end } => {
+                write!(f, "range start {} is after its end {}", start, end)
+            }
+            SegmentTreeError::EmptyRange => write!(f, "cannot query an empty range"),
+            SegmentTreeError::Overflow => write!(f, "result does not fit in an i64"),
+        }
+    }
 }
 
-#[derive(Clone, Debug)]
-struct Range {
-    // Perhaps ranges should be usize and not i32
-    // but that way we can't mark invalid ranges as -1
-    start: i32,
-    end: i32,
+impl Error for SegmentTreeError {}
+
+impl From<RangeError<usize>> for SegmentTreeError {
+    fn from(error: RangeError<usize>) -> Self {
+        match error {
+            RangeError::Reversed { start, end } => SegmentTreeError::ReversedRange { start, end },
+            RangeError::OutOfBounds { index, len } => SegmentTreeError::OutOfBounds { index, len },
+        }
+    }
 }
 
+/**
+ * Broken invariant found by SegmentTree::validate.
+ */
+pub use segment_tree::ValidationError;
+
+/**
+ * Segment tree supporting range add updates and range sum queries
+ *  in O(log n) time, on top of segment_tree::RangeAddTree.
+ *
+ * Elements wrap around like i64 arithmetic, while sums are computed
+ *  exactly on the wrapped elements (see segment_tree::Add).
+ */
 #[derive(Clone, Debug)]
-struct Node {
-    data: Data,           // Data contained in the node
-    range: Range,         // The range this node refers to
-    pending: Option<i32>, // Optional pending update on this node
+pub struct SegmentTree {
+    tree: RangeAddTree,
 }
 
 impl SegmentTree {
     /**
      * Builds a segment tree on the provided data and returns it.
      */
-    pub fn new(data: &Vec<i32>) -> Self {
-        // Add padding in case the data vector's length is not a power of 2
-        let smallest_exp = (data.len() as f64).log2().ceil() as u32;
-        let tree_size = 2 * usize::pow(2, smallest_exp) - 1;
+    pub fn new(data: &[i64]) -> Self {
+        let leaves: Vec<Sum> = data.iter().map(|&val| Sum::new(val)).collect();
 
-        let mut nodes: Vec<Node> = vec![
-            Node {
-                range: Range { start: -1, end: -1 },
-                data: Data { val: 0 },
-                pending: None
-            };
-            tree_size
-        ];
+        Self {
+            tree: RangeAddTree::new(&leaves),
+        }
+    }
 
-        SegmentTree::build_tree(&mut nodes, 0, data, 0, (data.len() - 1) as i32);
+    /**
+     * Returns the number of elements in the tree.
+     */
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
 
-        Self { nodes }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
     }
 
     /**
-     * Updates the elements in the range by val.
+     * Increases the elements in range by val.
+     *  Elements that overflow wrap around, see checked_add, and each
+     *  of them costs an extra O(log n) time.
      */
-    pub fn range_update(&mut self, i: usize, val: i32, start: i32, end: i32) {
-        if i >= self.nodes.len() {
-            panic!("Index out of bounds.");
-        }
+    pub fn add<R: RangeBounds<usize>>(
+        &mut self,
+        range: R,
+        val: i64,
+    ) -> Result<(), SegmentTreeError> {
+        self.tree.range_update(range, Add(val as i128))?;
 
-        if start > self.nodes[i].range.end || end < self.nodes[i].range.start {
-            //No overlap
-            return;
-        }
+        Ok(())
+    }
 
-        self.propagate_pending_update(i);
+    /**
+     * Increases the elements in range by val, unless that makes
+     *  any of them overflow, in which case the tree is left unchanged.
+     */
+    pub fn checked_add<R: RangeBounds<usize>>(
+        &mut self,
+        range: R,
+        val: i64,
+    ) -> Result<(), SegmentTreeError> {
+        let (start, end) = to_bounds(range, self.len())?;
 
-        if self.nodes[i].range.start >= start && self.nodes[i].range.end <= end {
-            // Total overlap
-            if self.nodes[i].range.start == self.nodes[i].range.end {
-                // Leaf: update it
-                self.nodes[i].data.val += val;
-            } else {
-                // Regular node: add update as pending
-                self.nodes[i].pending = Some(val);
-            }
-            return;
+        // Only the range's extremes can overflow first
+        let add = Add(val as i128);
+        if add.overflows(self.tree.range_query(start..end)?) {
+            return Err(SegmentTreeError::Overflow);
         }
 
-        // Partial overlap
-        self.range_update(2 * i + 1, val, start, end);
-        self.range_update(2 * i + 2, val, start, end);
+        self.tree.range_update(start..end, add)?;
+
+        Ok(())
     }
 
     /**
-     * Pushes all of the tree's leaves in the out vector,
-     *  propagating any pending update.
+     * Returns the sum of the elements in range.
      */
-    pub fn get_leaves(&mut self, i: usize, out: &mut Vec<i32>) {
-        if i > self.nodes.len() {
-            return;
+    pub fn sum<R: RangeBounds<usize>>(&mut self, range: R) -> Result<i64, SegmentTreeError> {
+        let data = self.tree.range_query(range)?;
+        if data.len == 0 {
+            return Err(SegmentTreeError::EmptyRange);
         }
 
-        self.propagate_pending_update(i);
+        i64::try_from(data.sum).map_err(|_| SegmentTreeError::Overflow)
+    }
 
-        if self.nodes[i].range.start == self.nodes[i].range.end {
-            out.push(self.nodes[i].data.val);
-        } else {
-            self.get_leaves(2 * i + 1, out);
-            self.get_leaves(2 * i + 2, out);
-        }
+    /**
+     * Returns all of the array's elements, propagating any pending update.
+     */
+    pub fn leaves(&mut self) -> Vec<i64> {
+        self.tree.get_leaves().iter().map(|leaf| leaf.min).collect()
     }
 
     /**
-     * Recursively build the segment tree
+     * Returns the element at position pos.
      */
-    fn build_tree(nodes: &mut Vec<Node>, i: usize, data: &Vec<i32>, start: i32, end: i32) -> Data {
-        if i >= nodes.len() || start == -1 || end == -1 {
-            panic!("TODO");
-        }
+    pub fn get(&mut self, pos: usize) -> Result<i64, SegmentTreeError> {
+        Ok(self.tree.get(pos)?.min)
+    }
 
-        // Leaf
-        if start == end {
-            nodes[i] = Node {
-                data: Data {
-                    // Leaves contain the array's elements
-                    val: data[start as usize],
-                },
-                range: Range { start, end },
-                pending: None,
-            };
-
-            return nodes[i].data;
-        }
+    /**
+     * Replaces the element at position pos with val.
+     */
+    pub fn set(&mut self, pos: usize, val: i64) -> Result<(), SegmentTreeError> {
+        self.update_with(pos, |_| val)?;
 
-        //Non-leaf node
-        let m: i32 = ((start as f64 + end as f64) / 2.0).floor() as i32;
-        SegmentTree::build_tree(nodes, 2 * i + 1, data, start, m);
-        SegmentTree::build_tree(nodes, 2 * i + 2, data, m + 1, end);
+        Ok(())
+    }
 
-        // Create and insert the node
-        nodes[i] = Node {
-            // Non-leaves contain dummy data
-            data: Data { val: 0 },
-            range: Range { start, end },
-            pending: None,
-        };
+    /**
+     * Replaces the element x at position pos with f(x) and returns the new value.
+     */
+    pub fn update_with<F: FnOnce(i64) -> i64>(
+        &mut self,
+        pos: usize,
+        f: F,
+    ) -> Result<i64, SegmentTreeError> {
+        let data = self.tree.update_with(pos, |data| Sum::new(f(data.min)))?;
 
-        nodes[i].data
+        Ok(data.min)
     }
 
     /**
-     * Applies any pending update on node i and propagates
-     * it to the node's children.
+     * Returns the tree in Graphviz DOT format, with every node's range,
+     *  data and pending update, nodes only made of padding excluded.
      */
-    fn propagate_pending_update(&mut self, i: usize) {
-        if i >= self.nodes.len() {
-            // Out of bounds
-            return;
-        }
+    pub fn to_dot(&self) -> String {
+        self.tree
+            .to_dot(|data| format!("sum {} | min {} | max {}", data.sum, data.min, data.max))
+    }
 
-        let mut node = &mut self.nodes[i];
-
-        match node.pending {
-            Some(val) => {
-                node.pending = None;
-
-                // Update node if it's a leaf
-                if node.range.start == node.range.end {
-                    node.data.val += val;
-                } else {
-                    //Set pending update on children
-                    let left_i = 2 * i + 1;
-                    if left_i < self.nodes.len() {
-                        self.nodes[left_i].pending = match self.nodes[left_i].pending {
-                            Some(old_val) => Some(old_val + val),
-                            None => Some(val),
-                        }
-                    }
-
-                    let right_i = 2 * i + 2;
-                    if right_i < self.nodes.len() {
-                        self.nodes[right_i].pending = match self.nodes[right_i].pending {
-                            Some(old_val) => Some(old_val + val),
-                            None => Some(val),
-                        }
-                    }
+    /**
+     * Walks the whole tree checking its invariants (see
+     *  segment_tree::SegmentTree::validate_with), and that
+     *  every leaf holds a single element.
+     */
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        self.tree.validate_with(|data| {
+            if *data != Sum::new(data.min) {
+                return Err(format!("leaf holds {:?}, not a single element", data));
+            }
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use std::cmp;
+
+    #[test]
+    fn test_range_sum() {
+        let mut truth: Vec<i64> = vec![5, -2, 7, 0, 3, 3, -8, 1, 4, 6];
+        let mut seg_tree = SegmentTree::new(&truth);
+
+        let updates = [(0, 9, 2), (3, 5, -4), (1, 1, 10), (6, 9, 3), (2, 7, 1)];
+        for (start, end, val) in updates {
+            seg_tree.add(start..=end, val).unwrap();
+            for x in &mut truth[start..=end] {
+                *x += val;
+            }
+            assert_eq!(seg_tree.validate(), Ok(()));
+
+            for l in 0..truth.len() {
+                for r in l..truth.len() {
+                    let expected: i64 = truth[l..=r].iter().sum();
+                    assert_eq!(seg_tree.sum(l..=r), Ok(expected));
                 }
             }
-            None => { /*Nothing to propagate*/ }
         }
+
+        assert_eq!(seg_tree.leaves(), truth);
+    }
+
+    #[test]
+    fn test_point_operations() {
+        let mut truth: Vec<i64> = vec![5, 1, 9, 3, 7, 2, 8];
+        let mut seg_tree = SegmentTree::new(&truth);
+
+        seg_tree.add(1..6, 4).unwrap();
+        for x in &mut truth[1..=5] {
+            *x += 4;
+        }
+
+        seg_tree.set(3, -10).unwrap();
+        truth[3] = -10;
+        assert_eq!(seg_tree.update_with(4, |x| x * 2), Ok(22));
+        truth[4] *= 2;
+
+        assert_eq!(seg_tree.validate(), Ok(()));
+
+        for (pos, val) in truth.iter().enumerate() {
+            assert_eq!(seg_tree.get(pos), Ok(*val));
+        }
+        assert_eq!(seg_tree.sum(..), Ok(truth.iter().sum::<i64>()));
+        assert_eq!(seg_tree.sum(2..=4), Ok(13 - 10 + 22));
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let mut seg_tree = SegmentTree::new(&[5, 1, 9]);
+        seg_tree.add(.., 2).unwrap();
+
+        let dot = seg_tree.to_dot();
+        assert!(dot.starts_with("digraph SegmentTree {"));
+        assert!(dot.contains(
+            "n0 [label=\"[0, 3] (1 padding) | sum 21 | min 3 | max 11 | pending: none\"];"
+        ));
+        assert!(dot.contains("n1 [label=\"[0, 1] | sum 10 | min 3 | max 7 | pending: add 2\"];"));
+        assert!(dot.contains("n0 -> n2;"));
+        // Node 6 only holds padding
+        assert!(!dot.contains("n6"));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(SegmentTree::new(&Vec::new()).validate(), Ok(()));
+
+        // Wrapped elements are a state the public API can produce
+        let mut seg_tree = SegmentTree::new(&[i64::MAX, 0, i64::MIN + 1]);
+        seg_tree.add(0..=0, 1).unwrap();
+        assert_eq!(seg_tree.validate(), Ok(()));
+        seg_tree.add(.., -2).unwrap();
+        assert_eq!(seg_tree.validate(), Ok(()));
+        assert_eq!(seg_tree.leaves(), vec![i64::MAX - 1, -2, i64::MAX]);
+
+        // Node 1 covers [0, 1] and keeps the update pending
+        let mut seg_tree = SegmentTree::new(&[5, 1, 9]);
+        seg_tree.add(..2, 3).unwrap();
+        assert_eq!(seg_tree.validate(), Ok(()));
+
+        seg_tree.set(1, -4).unwrap();
+        assert_eq!(seg_tree.validate(), Ok(()));
+        assert_eq!(seg_tree.leaves(), vec![8, -4, 9]);
+    }
+
+    #[test]
+    fn test_wrapping_add() {
+        let mut seg_tree = SegmentTree::new(&[i64::MAX, 0]);
+        seg_tree.add(0..=0, 1).unwrap();
+        assert_eq!(seg_tree.get(0), Ok(i64::MIN));
+        assert_eq!(seg_tree.sum(0..=0), Ok(i64::MIN));
+        assert_eq!(seg_tree.leaves(), vec![i64::MIN, 0]);
+        assert_eq!(seg_tree.validate(), Ok(()));
+
+        // Random additions near the limits, against a wrapping array
+        let mut rng = rand::thread_rng();
+        let mut truth: Vec<i64> = (0..50).map(|_| rng.gen()).collect();
+        let mut seg_tree = SegmentTree::new(&truth);
+        for _ in 0..500 {
+            let a = rng.gen_range(0..truth.len());
+            let b = rng.gen_range(0..truth.len());
+            let (start, end) = (cmp::min(a, b), cmp::max(a, b));
+
+            let val: i64 = rng.gen();
+            seg_tree.add(start..=end, val).unwrap();
+            for x in &mut truth[start..=end] {
+                *x = x.wrapping_add(val);
+            }
+            assert_eq!(seg_tree.validate(), Ok(()));
+
+            // Sums are exact on the wrapped elements
+            let expected: i128 = truth[start..=end].iter().map(|&x| x as i128).sum();
+            assert_eq!(
+                seg_tree.sum(start..=end),
+                i64::try_from(expected).map_err(|_| SegmentTreeError::Overflow)
+            );
+            assert_eq!(seg_tree.get(start), Ok(truth[start]));
+        }
+
+        assert_eq!(seg_tree.leaves(), truth);
+    }
+
+    #[test]
+    fn test_overflow() {
+        let mut seg_tree = SegmentTree::new(&[i64::MAX - 10, 0, i64::MIN + 10, 5]);
+
+        assert_eq!(
+            seg_tree.checked_add(..2, 11),
+            Err(SegmentTreeError::Overflow)
+        );
+        assert_eq!(
+            seg_tree.checked_add(1..=2, -11),
+            Err(SegmentTreeError::Overflow)
+        );
+        assert_eq!(
+            seg_tree.checked_add(.., 11),
+            Err(SegmentTreeError::Overflow)
+        );
+        assert_eq!(seg_tree.checked_add(1.., 10), Ok(()));
+        assert_eq!(seg_tree.checked_add(..3, -10), Ok(()));
+
+        // Failed updates leave the tree unchanged
+        assert_eq!(seg_tree.leaves(), vec![i64::MAX - 20, 0, i64::MIN + 10, 15]);
+
+        // Sums are computed exactly, and only fail if the result doesn't fit
+        assert_eq!(seg_tree.sum(..2), Ok(i64::MAX - 20));
+        assert_eq!(seg_tree.sum(..), Ok(4));
+        seg_tree.set(1, i64::MAX).unwrap();
+        assert_eq!(seg_tree.sum(..2), Err(SegmentTreeError::Overflow));
+        assert_eq!(seg_tree.sum(..3), Ok(i64::MAX - 11));
+
+        // Unchecked updates wrap around
+        seg_tree.add(1..=1, 1).unwrap();
+        assert_eq!(seg_tree.get(1), Ok(i64::MIN));
+    }
+
+    #[test]
+    fn test_range_errors() {
+        let mut seg_tree = SegmentTree::new(&[3, 1, 4, 1, 5]);
+
+        assert_eq!(
+            seg_tree.add(2..=5, 1),
+            Err(SegmentTreeError::OutOfBounds { index: 5, len: 5 })
+        );
+        let (start, end) = (3, 1);
+        assert_eq!(
+            seg_tree.sum(start..end),
+            Err(SegmentTreeError::ReversedRange { start: 3, end: 1 })
+        );
+        assert_eq!(seg_tree.sum(1..1), Err(SegmentTreeError::EmptyRange));
+        assert_eq!(
+            seg_tree.set(7, 0),
+            Err(SegmentTreeError::OutOfBounds { index: 7, len: 5 })
+        );
+        assert_eq!(seg_tree.leaves(), vec![3, 1, 4, 1, 5]);
     }
 }
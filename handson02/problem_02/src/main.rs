@@ -1,97 +1,179 @@
-use std::io;
-mod lib;
+use problem_02::batch::{self, BatchError, Operation, Query};
+use problem_02::fenwick::FenwickTree;
+use problem_02::SegmentTree;
+use std::env;
+use std::error::Error;
+use std::io::{self, BufWriter, Write};
+use std::process;
+use token_reader::TokenReader;
+
+const USAGE: &str =
+    "Usage: problem_02 [--backend segment_tree|fenwick|diff_array] [--checked] < input.txt
+
+    --checked  Stop with an error naming the offending operation if any value
+               overflows an i64 (segment_tree backend only)";
+
+/**
+ * Data structure used to apply the operations.
+ */
+#[derive(PartialEq)]
+enum Backend {
+    SegmentTree,
+    Fenwick,
+    DifferenceArray,
+}
+
+/**
+ * Array, operations and queries read from stdin.
+ */
+type Input = (Vec<i64>, Vec<Operation>, Vec<Query>);
 
-struct Operation {
-    start: i32, // Range start
-    end: i32,   // Range end
-    val: i32,   // Value to increase the range's elements by
+struct Options {
+    backend: Backend,
+    checked: bool, // Report overflows instead of wrapping around
 }
 
 fn main() {
-    let mut buffer = String::new();
-    let stdin = io::stdin();
+    let options = parse_options();
+
+    let (elems, ops, queries) = read_input().unwrap_or_else(|why| {
+        eprintln!("Error: {}", why);
+        process::exit(1);
+    });
+
+    // Number of times the ith operation must be repeated
+    let ops_count =
+        batch::operation_counts(ops.len(), &queries).unwrap_or_else(|why| fail(&ops, why));
+
+    // We can now perform each update just once instead of ops_count[i] times
+    let leaves: Vec<i64> = match options.backend {
+        Backend::SegmentTree if options.checked => {
+            batch::apply_with_segment_tree(&elems, &ops, &ops_count)
+                .unwrap_or_else(|why| fail(&ops, why))
+        }
+        Backend::SegmentTree => {
+            let mut seg_tree = SegmentTree::new(&elems);
+            for (op, count) in ops.iter().zip(&ops_count) {
+                seg_tree
+                    .add(op.start..=op.end, op.val.wrapping_mul(*count))
+                    .expect("Invalid operation range.");
+            }
+            seg_tree.leaves()
+        }
+        Backend::Fenwick => {
+            let mut fenwick = FenwickTree::new(&elems);
+            for (op, count) in ops.iter().zip(&ops_count) {
+                fenwick
+                    .add(op.start..=op.end, op.val.wrapping_mul(*count))
+                    .expect("Invalid operation range.");
+            }
+            fenwick.leaves()
+        }
+        Backend::DifferenceArray => batch::apply_with_difference_array(&elems, &ops, &ops_count)
+            .unwrap_or_else(|why| fail(&ops, why)),
+    };
 
-    // Read n, m and k
-    stdin.read_line(&mut buffer).expect("Failed to read line.");
-    let tmp: Vec<usize> = buffer
-        .split_whitespace()
-        .map(|x| x.parse().expect("Not a natural number!"))
-        .collect();
-    let n: usize = *tmp.first().expect("Missing input parameters.");
-    let m: usize = *tmp.get(1).expect("Missing input parameters.");
-    let k: usize = *tmp.get(2).expect("Missing input parameters.");
+    // Print result array
+    let mut out = BufWriter::new(io::stdout().lock());
+    for (i, leaf) in leaves.iter().enumerate() {
+        let separator = if i == 0 { "" } else { " " };
+        write!(out, "{}{}", separator, leaf).expect("Failed to write output.");
+    }
+    writeln!(out).expect("Failed to write output.");
+    out.flush().expect("Failed to write output.");
+}
 
-    buffer.clear();
+/**
+ * Reads the array, the operations and the queries from stdin.
+ */
+fn read_input() -> Result<Input, Box<dyn Error>> {
+    let mut reader = TokenReader::from_reader(io::stdin().lock())?;
 
-    // Read input array
-    stdin.read_line(&mut buffer).expect("Failed to read line.");
-    let elems: Vec<i32> = buffer
-        .split_whitespace()
-        .map(|x| x.parse().expect("Not an integer!"))
-        .collect();
+    // Read n, m and k
+    let n: usize = reader.next("array length n")?;
+    let m: usize = reader.next("number of operations m")?;
+    let k: usize = reader.next("number of queries k")?;
 
-    // Create a segment tree over the array of elements
-    let mut seg_tree = lib::SegmentTree::new(&elems);
+    // Read input array
+    let elems: Vec<i64> = reader.next_vec(n, "array element")?;
 
-    // Read operations
-    let mut ops: Vec<Operation> = Vec::with_capacity(m);
+    // Read operations, which use 1-based indices
+    let mut ops: Vec<Operation> = Vec::new();
     for _i in 0..m {
-        buffer.clear();
-
-        stdin.read_line(&mut buffer).expect("Failed to read line.");
-        let tmp: Vec<i32> = buffer
-            .split_whitespace()
-            .map(|x| x.parse().expect("Not an integer!"))
-            .collect();
-        let start: i32 = *tmp.first().expect("Malformed operation.") - 1;
-        let end: i32 = *tmp.get(1).expect("Malformed operation.") - 1;
-        let val: i32 = *tmp.get(2).expect("Malformed operation.");
+        let start = reader.next_in(1..=n, "operation start")? - 1;
+        let end = reader.next_in(start + 1..=n, "operation end")? - 1;
+        let val: i64 = reader.next("operation value")?;
 
         ops.push(Operation { start, end, val });
     }
 
-    // Difference array initialized to 0
-    let mut ops_diff_array: Vec<i32> = vec![0; m + 1];
-
-    // Read queries
+    // Read queries, which use 1-based indices too
+    let mut queries: Vec<Query> = Vec::new();
     for _i in 0..k {
-        buffer.clear();
-
-        stdin.read_line(&mut buffer).expect("Failed to read line.");
-        let tmp: Vec<i32> = buffer
-            .split_whitespace()
-            .map(|x| x.parse().expect("Not a natural number!"))
-            .collect();
-        let start: i32 = *tmp.first().expect("Malformed query.") - 1;
-        let end: i32 = *tmp.get(1).expect("Malformed query.") - 1;
-
-        // Update difference array
-        ops_diff_array[start as usize] += 1;
-        ops_diff_array[end as usize + 1] -= 1;
+        let start = reader.next_in(1..=m, "query start")? - 1;
+        let end = reader.next_in(start + 1..=m, "query end")? - 1;
+
+        queries.push(Query { start, end });
     }
+    reader.finish()?;
 
-    // Create a new array in which to hold the number of times
-    //  the ith operation must be repeated, according to the values in
-    //  the difference array.
-    let mut ops_count: Vec<i32> = Vec::with_capacity(m);
-    for i in 0..m {
-        let op = &ops[i];
-        if i == 0 {
-            ops_count.push(ops_diff_array[i]);
-        } else {
-            ops_count.push(ops_diff_array[i] + ops_count[i - 1]);
+    Ok((elems, ops, queries))
+}
+
+/**
+ * Reads the options from the command line, defaulting to the unchecked segment tree.
+ */
+fn parse_options() -> Options {
+    let mut options = Options {
+        backend: Backend::SegmentTree,
+        checked: false,
+    };
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--checked" => options.checked = true,
+            "--backend" => {
+                options.backend = match args.next().as_deref() {
+                    Some("segment_tree") => Backend::SegmentTree,
+                    Some("fenwick") => Backend::Fenwick,
+                    Some("diff_array") => Backend::DifferenceArray,
+                    _ => usage(),
+                }
+            }
+            _ => usage(),
         }
+    }
 
-        // We can now perform the update just once instead of ops_count[i] times
-        seg_tree.range_update(0, op.val * ops_count[i], op.start, op.end);
+    if options.checked && options.backend != Backend::SegmentTree {
+        usage();
     }
 
-    // Print result array
-    let mut leaves: Vec<i32> = Vec::with_capacity(n);
-    seg_tree.get_leaves(0, &mut leaves);
-    for leaf in leaves.iter().take(n - 1) {
-        print!("{} ", leaf);
+    options
+}
+
+/**
+ * Reports why the operations could not be applied and exits.
+ */
+fn fail(ops: &[Operation], why: BatchError) -> ! {
+    match why {
+        BatchError::Overflow { operation, count } => {
+            let op = &ops[operation];
+            eprintln!(
+                "Operation {} ({} {} {}), applied {} times, makes an element overflow an i64.",
+                operation + 1,
+                op.start + 1,
+                op.end + 1,
+                op.val,
+                count
+            );
+        }
+        why => eprintln!("Invalid input: {}.", why),
     }
-    print!("{}", leaves[n - 1]);
-    println!();
+    process::exit(1);
+}
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(1);
 }
@@ -2,6 +2,12 @@
 
 dir="tests"
 
+for backend in segment_tree fenwick diff_array; do
+    for i in {0..2}; do
+        cargo run -- --backend $backend < "$dir/input$i.txt" | diff - "$dir/output$i".txt
+    done
+done
+
 for i in {0..2}; do
-    cargo run < "$dir/input$i.txt" | diff - "$dir/output$i".txt
+    cargo run -- --checked < "$dir/input$i.txt" | diff - "$dir/output$i".txt
 done
@@ -0,0 +1,332 @@
+use std::ops::{Bound, RangeBounds};
+
+use crate::{to_inclusive, Monoid, RangeError, Tag};
+
+#[derive(Clone, Debug)]
+struct Node<M, T> {
+    data: M,            // Combined value of the node's range, pending update included
+    pending: Option<T>, // Optional update yet to be pushed to the node's children
+    left: usize,        // Index of the left child, 0 if not created yet
+    right: usize,       // Index of the right child, 0 if not created yet
+}
+
+/**
+ * Segment tree over the positions <0, len - 1>, with len up to u64::MAX,
+ *  where every element starts with the same value.
+ *
+ * Nodes are only created when an update or a query needs to visit
+ *  them, so each operation allocates O(log len) nodes at most,
+ *  regardless of the size of the range of positions.
+ */
+#[derive(Debug)]
+pub struct DynamicSegmentTree<M, T> {
+    nodes: Vec<Node<M, T>>, // Arena holding every node, the root being nodes[0]
+    init: M,                // Initial value of every element
+    len: u64,
+}
+
+impl<M: Monoid, T: Tag<M>> DynamicSegmentTree<M, T> {
+    /**
+     * Builds a segment tree on len elements, all equal to init, and returns it.
+     */
+    pub fn new(len: u64, init: M) -> Self {
+        let mut tree = Self {
+            nodes: Vec::new(),
+            init,
+            len,
+        };
+        tree.push_node(len);
+
+        tree
+    }
+
+    /**
+     * Returns the number of elements in the tree.
+     */
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /**
+     * Returns the number of nodes allocated so far.
+     */
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /**
+     * Applies tag to the elements in range.
+     */
+    pub fn range_update<R: RangeBounds<u64>>(
+        &mut self,
+        range: R,
+        tag: T,
+    ) -> Result<(), RangeError<u64>> {
+        if let Some((start, end)) = to_inclusive(range, self.len)? {
+            self.update_node(0, 0, self.len - 1, start, end, tag);
+        }
+
+        Ok(())
+    }
+
+    /**
+     * Returns the combination of the elements in range.
+     */
+    pub fn range_query<R: RangeBounds<u64>>(&mut self, range: R) -> Result<M, RangeError<u64>> {
+        match to_inclusive(range, self.len)? {
+            Some((start, end)) => Ok(self.query_node(0, 0, self.len - 1, start, end)),
+            None => Ok(M::identity()),
+        }
+    }
+
+    fn update_node(&mut self, i: usize, lo: u64, hi: u64, start: u64, end: u64, tag: T) {
+        if start > hi || end < lo || tag.is_noop(&self.nodes[i].data) {
+            // No overlap, or no element is affected
+            return;
+        }
+
+        if start <= lo && end >= hi && (lo == hi || tag.can_apply(&self.nodes[i].data)) {
+            // Total overlap and the update follows from the node's data:
+            // stop here and leave the update pending
+            self.apply_tag(i, lo == hi, tag);
+            return;
+        }
+
+        // Partial overlap, or the update has to be broken down
+        let m = lo + (hi - lo) / 2;
+        self.propagate_pending_update(i, lo, hi);
+        let (left, right) = (self.nodes[i].left, self.nodes[i].right);
+        self.update_node(left, lo, m, start, end, tag);
+        self.update_node(right, m + 1, hi, start, end, tag);
+
+        self.nodes[i].data = M::combine(self.nodes[left].data, self.nodes[right].data);
+    }
+
+    fn query_node(&mut self, i: usize, lo: u64, hi: u64, start: u64, end: u64) -> M {
+        if start > hi || end < lo {
+            // No overlap
+            return M::identity();
+        }
+
+        if start <= lo && end >= hi {
+            // Total overlap
+            return self.nodes[i].data;
+        }
+
+        // Partial overlap
+        let m = lo + (hi - lo) / 2;
+        self.propagate_pending_update(i, lo, hi);
+        let (left, right) = (self.nodes[i].left, self.nodes[i].right);
+        let left = self.query_node(left, lo, m, start, end);
+        let right = self.query_node(right, m + 1, hi, start, end);
+
+        M::combine(left, right)
+    }
+
+    /**
+     * Applies tag to the whole range of node i, recording it
+     *  as pending for the node's children.
+     */
+    fn apply_tag(&mut self, i: usize, is_leaf: bool, tag: T) {
+        let node = &mut self.nodes[i];
+        node.data = tag.apply(node.data);
+
+        if !is_leaf {
+            node.pending = Some(match node.pending {
+                Some(old_tag) => tag.compose(old_tag),
+                None => tag,
+            });
+        }
+    }
+
+    /**
+     * Creates the children of node i, covering <lo, hi>, if they don't
+     *  exist yet, then pushes any pending update on the node to them.
+     */
+    fn propagate_pending_update(&mut self, i: usize, lo: u64, hi: u64) {
+        let m = lo + (hi - lo) / 2;
+        if self.nodes[i].left == 0 {
+            self.nodes[i].left = self.push_node(m - lo + 1);
+            self.nodes[i].right = self.push_node(hi - m);
+        }
+
+        if let Some(tag) = self.nodes[i].pending.take() {
+            let (left, right) = (self.nodes[i].left, self.nodes[i].right);
+            self.apply_tag(left, lo == m, tag);
+            self.apply_tag(right, m + 1 == hi, tag);
+        }
+    }
+
+    /**
+     * Allocates a node covering len untouched elements and returns its index.
+     */
+    fn push_node(&mut self, len: u64) -> usize {
+        self.nodes.push(Node {
+            data: repeat(self.init, len),
+            pending: None,
+            left: 0,
+            right: 0,
+        });
+
+        self.nodes.len() - 1
+    }
+}
+
+/**
+ * Returns the combination of n copies of x, in O(log n) combine operations.
+ */
+fn repeat<M: Monoid>(mut x: M, mut n: u64) -> M {
+    let mut result = M::identity();
+    while n > 0 {
+        if n & 1 == 1 {
+            result = M::combine(result, x);
+        }
+        x = M::combine(x, x);
+        n >>= 1;
+    }
+
+    result
+}
+
+/**
+ * Maps a set of coordinates to their ranks <0, len - 1>, so that offline
+ *  workloads touching few, far apart, positions can run on a tree of
+ *  len elements instead of one spanning the whole coordinate range.
+ */
+#[derive(Clone, Debug)]
+pub struct CoordinateCompression<C> {
+    coords: Vec<C>, // Sorted and without duplicates
+}
+
+impl<C: Ord + Copy> CoordinateCompression<C> {
+    /**
+     * Builds the mapping of the provided coordinates, in any order
+     *  and possibly with duplicates.
+     */
+    pub fn new<I: IntoIterator<Item = C>>(coords: I) -> Self {
+        let mut coords: Vec<C> = coords.into_iter().collect();
+        coords.sort_unstable();
+        coords.dedup();
+
+        Self { coords }
+    }
+
+    /**
+     * Returns the number of distinct coordinates.
+     */
+    pub fn len(&self) -> usize {
+        self.coords.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.coords.is_empty()
+    }
+
+    /**
+     * Returns the rank of coord, if it's one of the known coordinates.
+     */
+    pub fn index_of(&self, coord: C) -> Option<usize> {
+        self.coords.binary_search(&coord).ok()
+    }
+
+    /**
+     * Returns the coordinate with rank index.
+     */
+    pub fn coord(&self, index: usize) -> C {
+        self.coords[index]
+    }
+
+    /**
+     * Returns the <start, end> (exclusive) range of ranks of the
+     *  known coordinates that fall in range.
+     */
+    pub fn compress_range<R: RangeBounds<C>>(&self, range: R) -> (usize, usize) {
+        let start = match range.start_bound() {
+            Bound::Included(start) => self.coords.partition_point(|c| c < start),
+            Bound::Excluded(start) => self.coords.partition_point(|c| c <= start),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(end) => self.coords.partition_point(|c| c <= end),
+            Bound::Excluded(end) => self.coords.partition_point(|c| c < end),
+            Bound::Unbounded => self.coords.len(),
+        };
+
+        (start, end.max(start))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Add, Chmin, MinMax, SegmentTree, Sum};
+    use rand::Rng;
+    use std::cmp;
+
+    #[test]
+    fn test_against_dense() {
+        let mut rng = rand::thread_rng();
+        let mut dynamic: DynamicSegmentTree<MinMax, Chmin> =
+            DynamicSegmentTree::new(100, MinMax::new(500));
+        let mut dense: SegmentTree<MinMax, Chmin> = SegmentTree::new(&[MinMax::new(500); 100]);
+
+        for _ in 0..1000 {
+            let a = rng.gen_range(0..100);
+            let b = rng.gen_range(0..100);
+            let (start, end) = (cmp::min(a, b), cmp::max(a, b));
+
+            if rng.gen_bool(0.5) {
+                let val = rng.gen_range(0..1000);
+                dynamic
+                    .range_update(start as u64..=end as u64, Chmin(val))
+                    .unwrap();
+                dense.range_update(start..=end, Chmin(val)).unwrap();
+            } else {
+                assert_eq!(
+                    dynamic.range_query(start as u64..=end as u64).unwrap(),
+                    dense.range_query(start..=end).unwrap()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_huge_range() {
+        let len = 1_000_000_000_000_000_000;
+        let mut tree: DynamicSegmentTree<Sum, Add> = DynamicSegmentTree::new(len, Sum::new(0));
+
+        tree.range_update(10..20, Add(1)).unwrap();
+        tree.range_update(len - 5.., Add(3)).unwrap();
+        tree.range_update(15..=len - 3, Add(-2)).unwrap();
+
+        assert_eq!(
+            tree.range_query(..).unwrap().sum,
+            10 + 15 - 2 * (len as i128 - 17)
+        );
+        assert_eq!(tree.range_query(len - 2..).unwrap().sum, 6);
+        assert_eq!(tree.range_query(0..15).unwrap().sum, 5);
+        assert!(tree.node_count() < 1000);
+
+        assert_eq!(
+            tree.range_query(len - 1..=len).map(|data| data.sum),
+            Err(RangeError::OutOfBounds { index: len, len })
+        );
+    }
+
+    #[test]
+    fn test_coordinate_compression() {
+        let compression = CoordinateCompression::new([40, 10, 1_000_000_000_000u64, 10, 25]);
+
+        assert_eq!(compression.len(), 4);
+        assert_eq!(compression.index_of(25), Some(1));
+        assert_eq!(compression.index_of(26), None);
+        assert_eq!(compression.coord(3), 1_000_000_000_000);
+        assert_eq!(compression.compress_range(11..=40), (1, 3));
+        assert_eq!(compression.compress_range(..40), (0, 2));
+        assert_eq!(compression.compress_range(26..30), (2, 2));
+    }
+}
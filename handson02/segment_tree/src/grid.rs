@@ -0,0 +1,320 @@
+use std::ops::RangeBounds;
+
+use crate::{to_bounds, Monoid, RangeError};
+
+/**
+ * Segment tree of segment trees over a rows x cols grid, supporting point
+ *  updates and rectangle queries in O(log rows * log cols) time.
+ *
+ * Both levels use the bottom-up layout: the grid's cells are stored in
+ *  t[rows..2 * rows][cols..2 * cols], row node i's children are rows 2i
+ *  and 2i + 1 and the same holds for columns.
+ * Cells are combined out of order, so M::combine must be commutative
+ *  (as it is for MinMax and Sum).
+ */
+#[derive(Debug)]
+pub struct SegmentTree2D<M> {
+    rows: usize,
+    cols: usize,
+    t: Vec<M>, // 2 * rows x 2 * cols matrix, stored by row
+}
+
+impl<M: Monoid> SegmentTree2D<M> {
+    /**
+     * Builds a segment tree on the provided grid and returns it.
+     *  Panics if the grid's rows don't have all the same length.
+     */
+    pub fn new(grid: &[Vec<M>]) -> Self {
+        let rows = grid.len();
+        let cols = grid.first().map_or(0, |row| row.len());
+        if grid.iter().any(|row| row.len() != cols) {
+            panic!("All the rows of the grid must have the same length.");
+        }
+
+        let mut tree = Self {
+            rows,
+            cols,
+            t: vec![M::identity(); 4 * rows * cols],
+        };
+
+        for (i, row) in grid.iter().enumerate() {
+            let r = rows + i;
+            tree.row_mut(r)[cols..].copy_from_slice(row);
+            for c in (1..cols).rev() {
+                let data = M::combine(tree.at(r, 2 * c), tree.at(r, 2 * c + 1));
+                tree.set_at(r, c, data);
+            }
+        }
+
+        for r in (1..rows).rev() {
+            for c in 1..2 * cols {
+                let data = M::combine(tree.at(2 * r, c), tree.at(2 * r + 1, c));
+                tree.set_at(r, c, data);
+            }
+        }
+
+        tree
+    }
+
+    /**
+     * Returns the number of rows of the grid.
+     */
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /**
+     * Returns the number of columns of the grid.
+     */
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /**
+     * Returns the cell at <row, col>.
+     */
+    pub fn get(&self, row: usize, col: usize) -> Result<M, RangeError<usize>> {
+        self.check_cell(row, col)?;
+
+        Ok(self.at(self.rows + row, self.cols + col))
+    }
+
+    /**
+     * Replaces the cell at <row, col> with val.
+     */
+    pub fn set(&mut self, row: usize, col: usize, val: M) -> Result<(), RangeError<usize>> {
+        self.check_cell(row, col)?;
+
+        // Update the column tree of the cell's row...
+        let r = self.rows + row;
+        let mut c = self.cols + col;
+        self.set_at(r, c, val);
+        while c > 1 {
+            c >>= 1;
+            let data = M::combine(self.at(r, 2 * c), self.at(r, 2 * c + 1));
+            self.set_at(r, c, data);
+        }
+
+        // ...and then the column trees of its ancestors
+        let mut r = r;
+        while r > 1 {
+            r >>= 1;
+            let mut c = self.cols + col;
+            while c > 0 {
+                let data = M::combine(self.at(2 * r, c), self.at(2 * r + 1, c));
+                self.set_at(r, c, data);
+                c >>= 1;
+            }
+        }
+
+        Ok(())
+    }
+
+    /**
+     * Returns the combination of the cells in the rectangle
+     *  spanning the given ranges of rows and columns.
+     */
+    pub fn query<R: RangeBounds<usize>, C: RangeBounds<usize>>(
+        &self,
+        rows: R,
+        cols: C,
+    ) -> Result<M, RangeError<usize>> {
+        let (row_start, row_end) = to_bounds(rows, self.rows)?;
+        let (col_start, col_end) = to_bounds(cols, self.cols)?;
+
+        let mut result = M::identity();
+        let (mut l, mut r) = (row_start + self.rows, row_end + self.rows);
+        while l < r {
+            if l & 1 == 1 {
+                result = M::combine(result, self.query_row(l, col_start, col_end));
+                l += 1;
+            }
+            if r & 1 == 1 {
+                r -= 1;
+                result = M::combine(result, self.query_row(r, col_start, col_end));
+            }
+            l >>= 1;
+            r >>= 1;
+        }
+
+        Ok(result)
+    }
+
+    /**
+     * Returns the combination of the <start, end> (exclusive) columns
+     *  in the column tree of row node r.
+     */
+    fn query_row(&self, r: usize, start: usize, end: usize) -> M {
+        let mut result = M::identity();
+        let (mut l, mut c) = (start + self.cols, end + self.cols);
+        while l < c {
+            if l & 1 == 1 {
+                result = M::combine(result, self.at(r, l));
+                l += 1;
+            }
+            if c & 1 == 1 {
+                c -= 1;
+                result = M::combine(result, self.at(r, c));
+            }
+            l >>= 1;
+            c >>= 1;
+        }
+
+        result
+    }
+
+    /**
+     * Returns an error about the row or column of <row, col>
+     *  that is out of bounds, if any.
+     */
+    fn check_cell(&self, row: usize, col: usize) -> Result<(), RangeError<usize>> {
+        if row >= self.rows {
+            return Err(RangeError::OutOfBounds {
+                index: row,
+                len: self.rows,
+            });
+        }
+        if col >= self.cols {
+            return Err(RangeError::OutOfBounds {
+                index: col,
+                len: self.cols,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn at(&self, r: usize, c: usize) -> M {
+        self.t[r * 2 * self.cols + c]
+    }
+
+    fn set_at(&mut self, r: usize, c: usize, data: M) {
+        self.t[r * 2 * self.cols + c] = data;
+    }
+
+    fn row_mut(&mut self, r: usize) -> &mut [M] {
+        let width = 2 * self.cols;
+        &mut self.t[r * width..(r + 1) * width]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MinMax, Sum};
+    use rand::Rng;
+    use std::cmp;
+
+    /**
+     * Returns a random, possibly empty, <start, end> (exclusive) range of <0, len - 1>.
+     */
+    fn random_range(rng: &mut impl Rng, len: usize) -> (usize, usize) {
+        let a = rng.gen_range(0..=len);
+        let b = rng.gen_range(0..=len);
+        (cmp::min(a, b), cmp::max(a, b))
+    }
+
+    #[test]
+    fn test_min_max_grid() {
+        let mut rng = rand::thread_rng();
+
+        for (rows, cols) in [(1, 1), (1, 9), (7, 1), (13, 20), (32, 5)] {
+            let mut truth: Vec<Vec<i32>> = (0..rows)
+                .map(|_| (0..cols).map(|_| rng.gen_range(-1000..1000)).collect())
+                .collect();
+            let grid: Vec<Vec<MinMax>> = truth
+                .iter()
+                .map(|row| row.iter().map(|&x| MinMax::new(x)).collect())
+                .collect();
+            let mut tree = SegmentTree2D::new(&grid);
+
+            for _ in 0..500 {
+                if rng.gen_bool(0.3) {
+                    let (row, col) = (rng.gen_range(0..rows), rng.gen_range(0..cols));
+                    let val = rng.gen_range(-1000..1000);
+                    tree.set(row, col, MinMax::new(val)).unwrap();
+                    truth[row][col] = val;
+                    assert_eq!(tree.get(row, col), Ok(MinMax::new(val)));
+                } else {
+                    let (row_start, row_end) = random_range(&mut rng, rows);
+                    let (col_start, col_end) = random_range(&mut rng, cols);
+                    let cells = truth[row_start..row_end]
+                        .iter()
+                        .flat_map(|row| &row[col_start..col_end]);
+
+                    let expected = MinMax {
+                        min: cells.clone().copied().min().unwrap_or(i32::MAX),
+                        max: cells.copied().max().unwrap_or(i32::MIN),
+                    };
+                    let result = tree.query(row_start..row_end, col_start..col_end);
+                    assert_eq!(result, Ok(expected));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_sum_grid() {
+        let mut rng = rand::thread_rng();
+        let (rows, cols) = (17, 11);
+        let mut truth: Vec<Vec<i64>> = vec![vec![0; cols]; rows];
+        let mut tree = SegmentTree2D::new(&vec![vec![Sum::new(0); cols]; rows]);
+
+        for _ in 0..2000 {
+            let (row, col) = (rng.gen_range(0..rows), rng.gen_range(0..cols));
+            let val = rng.gen_range(-1000..1000);
+            tree.set(row, col, Sum::new(val)).unwrap();
+            truth[row][col] = val;
+
+            let (row_start, row_end) = random_range(&mut rng, rows);
+            let (col_start, col_end) = random_range(&mut rng, cols);
+            let expected: i64 = truth[row_start..row_end]
+                .iter()
+                .flat_map(|row| &row[col_start..col_end])
+                .sum();
+            let expected = expected as i128;
+            assert_eq!(
+                tree.query(row_start..row_end, col_start..col_end)
+                    .unwrap()
+                    .sum,
+                expected
+            );
+        }
+
+        assert_eq!(
+            tree.query(.., ..).unwrap().sum,
+            truth.iter().flatten().sum::<i64>() as i128
+        );
+        assert_eq!(tree.query(3..=3, 4..=4), tree.get(3, 4));
+    }
+
+    #[test]
+    fn test_grid_errors() {
+        let mut tree = SegmentTree2D::new(&vec![vec![Sum::new(1); 4]; 3]);
+
+        assert_eq!(
+            tree.get(3, 0),
+            Err(RangeError::OutOfBounds { index: 3, len: 3 })
+        );
+        assert_eq!(
+            tree.set(0, 4, Sum::new(2)),
+            Err(RangeError::OutOfBounds { index: 4, len: 4 })
+        );
+        assert_eq!(
+            tree.query(.., 1..=4),
+            Err(RangeError::OutOfBounds { index: 4, len: 4 })
+        );
+        let (start, end) = (2, 1);
+        assert_eq!(
+            tree.query(start..end, ..),
+            Err(RangeError::Reversed { start: 2, end: 1 })
+        );
+        assert_eq!(tree.query(.., ..).map(|data| data.sum), Ok(12));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_ragged_grid() {
+        SegmentTree2D::new(&[vec![Sum::new(1), Sum::new(2)], vec![Sum::new(3)]]);
+    }
+}
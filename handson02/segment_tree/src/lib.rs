@@ -0,0 +1,1410 @@
+use std::cmp;
+use std::error::Error;
+use std::fmt;
+use std::ops::{Bound, RangeBounds};
+
+pub mod dynamic;
+pub mod grid;
+pub mod persistent;
+
+/**
+ * Values stored in the tree's nodes: an associative combine
+ *  operation with an identity element.
+ */
+pub trait Monoid: Copy {
+    fn identity() -> Self;
+    fn combine(a: Self, b: Self) -> Self;
+}
+
+/**
+ * Lazy updates on ranges of M values.
+ */
+pub trait Tag<M>: Copy {
+    /**
+     * Applies the update to the combined value of a whole range.
+     *  The trees only call it on single elements and on ranges for which
+     *  can_apply holds.
+     */
+    fn apply(&self, data: M) -> M;
+
+    /**
+     * Returns the update equivalent to applying older first and then self.
+     */
+    fn compose(&self, older: Self) -> Self;
+
+    /**
+     * Returns whether the update leaves every element of a range with
+     *  combined value data unchanged, so that the trees can skip the
+     *  range altogether (the "break condition" of segment tree beats).
+     */
+    fn is_noop(&self, _data: &M) -> bool {
+        false
+    }
+
+    /**
+     * Returns whether apply can compute the update's effect on a range of
+     *  several elements from their combined value data alone (the "tag
+     *  condition"). If not, the trees break the update down to the range's
+     *  children and combine their results instead.
+     * Once it holds for a range, it must hold for every part of it, and keep
+     *  holding after more updates are composed with this one, so that the
+     *  update can be left pending on the range.
+     */
+    fn can_apply(&self, _data: &M) -> bool {
+        true
+    }
+}
+
+/**
+ * Integer type used for the positions of a tree.
+ */
+pub trait Position: Copy + Ord + fmt::Display {
+    const ZERO: Self;
+
+    fn checked_succ(self) -> Option<Self>;
+    fn pred(self) -> Self;
+}
+
+impl Position for usize {
+    const ZERO: Self = 0;
+
+    fn checked_succ(self) -> Option<Self> {
+        self.checked_add(1)
+    }
+
+    fn pred(self) -> Self {
+        self - 1
+    }
+}
+
+impl Position for u64 {
+    const ZERO: Self = 0;
+
+    fn checked_succ(self) -> Option<Self> {
+        self.checked_add(1)
+    }
+
+    fn pred(self) -> Self {
+        self - 1
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RangeError<P> {
+    // Range whose start comes after its (exclusive) end
+    Reversed { start: P, end: P },
+    // Range reaching past the last position, index being its last one
+    OutOfBounds { index: P, len: P },
+}
+
+impl<P: fmt::Display> fmt::Display for RangeError<P> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RangeError::Reversed { start, end } => {
+                write!(f, "range start {} is after its end {}", start, end)
+            }
+            RangeError::OutOfBounds { index, len } => {
+                write!(f, "index {} is out of bounds for length {}", index, len)
+            }
+        }
+    }
+}
+
+impl<P: fmt::Debug + fmt::Display> Error for RangeError<P> {}
+
+/**
+ * Converts range into the equivalent <start, end> (exclusive) pair,
+ *  checking that it's a valid range of an array with len elements.
+ */
+pub fn to_bounds<P: Position, R: RangeBounds<P>>(
+    range: R,
+    len: P,
+) -> Result<(P, P), RangeError<P>> {
+    // A bound that can't be made exclusive is the largest position of P,
+    // which no array of at most P::MAX elements has
+    let start = match range.start_bound() {
+        Bound::Included(&start) => start,
+        Bound::Excluded(&start) => start
+            .checked_succ()
+            .ok_or(RangeError::OutOfBounds { index: start, len })?,
+        Bound::Unbounded => P::ZERO,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&end) => end
+            .checked_succ()
+            .ok_or(RangeError::OutOfBounds { index: end, len })?,
+        Bound::Excluded(&end) => end,
+        Bound::Unbounded => len,
+    };
+
+    if start > end {
+        return Err(RangeError::Reversed { start, end });
+    }
+
+    if end > len {
+        return Err(RangeError::OutOfBounds {
+            index: end.pred(),
+            len,
+        });
+    }
+
+    Ok((start, end))
+}
+
+/**
+ * Same as to_bounds, but returns the equivalent <start, end> (inclusive)
+ *  pair, or None if the range is empty.
+ */
+pub(crate) fn to_inclusive<P: Position, R: RangeBounds<P>>(
+    range: R,
+    len: P,
+) -> Result<Option<(P, P)>, RangeError<P>> {
+    let (start, end) = to_bounds(range, len)?;
+    if start == end {
+        return Ok(None);
+    }
+
+    Ok(Some((start, end.pred())))
+}
+
+/**
+ * Broken invariant found by SegmentTree::validate.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidationError {
+    pub node: usize,  // Position of the offending node in the tree's array
+    pub start: usize, // Range recorded in the node
+    pub end: usize,
+    pub reason: String,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "node {} ([{}, {}]): {}",
+            self.node, self.start, self.end, self.reason
+        )
+    }
+}
+
+impl Error for ValidationError {}
+
+/**
+ * Read-only view of a node, for debugging purposes.
+ */
+#[derive(Clone, Debug)]
+pub struct NodeView<M, T> {
+    pub index: usize, // Position of the node in the tree's array
+    pub depth: usize,
+    pub start: usize,   // First position of the node's range
+    pub end: usize,     // Last position of the node's range, padding included
+    pub padding: usize, // Number of padding positions at the end of the range
+    pub data: M,
+    pub pending: Option<T>, // Update applied to the node but not to its children yet
+}
+
+impl<M, T: fmt::Display> NodeView<M, T> {
+    /**
+     * Returns a description of the pending update, such as "chmin 4" or "none".
+     */
+    pub fn describe_pending(&self) -> String {
+        match &self.pending {
+            Some(tag) => tag.to_string(),
+            None => String::from("none"),
+        }
+    }
+
+    /**
+     * Returns a note such as " (1 padding)" to append to the node's range,
+     *  or an empty string if the range holds no padding.
+     */
+    pub fn describe_padding(&self) -> String {
+        match self.padding {
+            0 => String::new(),
+            padding => format!(" ({} padding)", padding),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Range {
+    start: usize,
+    end: usize,
+}
+
+#[derive(Clone, Debug)]
+struct Node<M, T> {
+    data: M,            // Combined value of the node's range, pending update included
+    range: Range,       // The range this node refers to
+    pending: Option<T>, // Optional update yet to be pushed to the node's children
+}
+
+/**
+ * Lazy segment tree on M values, with T updates on ranges.
+ *
+ * Updates and queries take O(log n) time, plus the time spent breaking
+ *  updates down on the ranges where T::can_apply doesn't hold, which
+ *  segment tree beats bound to an amortized O(log n) or O(log^2 n).
+ *
+ * The leaves span a power of 2 capacity, the ones past the last element
+ *  holding M::identity(), so that elements can be appended in O(log n) time
+ *  and the capacity doubled (in amortized O(1) time) once it runs out.
+ */
+#[derive(Clone, Debug)]
+pub struct SegmentTree<M, T> {
+    nodes: Vec<Node<M, T>>,
+    len: usize, // Number of elements, the remaining leaves are padding
+}
+
+impl<M: Monoid, T: Tag<M>> SegmentTree<M, T> {
+    /**
+     * Builds a segment tree on the provided data and returns it.
+     */
+    pub fn new(data: &[M]) -> Self {
+        // Add padding in case the data vector's length is not a power of 2
+        let capacity = cmp::max(data.len().next_power_of_two(), 1);
+        let mut nodes: Vec<Node<M, T>> = vec![SegmentTree::padding_node(); 2 * capacity - 1];
+
+        SegmentTree::build_tree(&mut nodes, 0, data, 0, capacity - 1);
+
+        Self {
+            nodes,
+            len: data.len(),
+        }
+    }
+
+    /**
+     * Returns the number of elements in the tree.
+     */
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /**
+     * Returns the number of elements the tree can hold before having to grow.
+     */
+    pub fn capacity(&self) -> usize {
+        self.nodes.len().div_ceil(2)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /**
+     * Applies tag to the elements in range.
+     */
+    pub fn range_update<R: RangeBounds<usize>>(
+        &mut self,
+        range: R,
+        tag: T,
+    ) -> Result<(), RangeError<usize>> {
+        if let Some((start, end)) = to_inclusive(range, self.len)? {
+            self.update_node(0, start, end, tag);
+        }
+
+        Ok(())
+    }
+
+    /**
+     * Returns the combination of the elements in range.
+     */
+    pub fn range_query<R: RangeBounds<usize>>(&mut self, range: R) -> Result<M, RangeError<usize>> {
+        match to_inclusive(range, self.len)? {
+            Some((start, end)) => Ok(self.query_node(0, start, end)),
+            None => Ok(M::identity()),
+        }
+    }
+
+    /**
+     * Returns the element at position pos.
+     */
+    pub fn get(&mut self, pos: usize) -> Result<M, RangeError<usize>> {
+        self.check_position(pos)?;
+
+        let mut i = 0;
+        while self.nodes[i].range.start != self.nodes[i].range.end {
+            self.propagate_pending_update(i);
+
+            i = if pos <= self.nodes[2 * i + 1].range.end {
+                2 * i + 1
+            } else {
+                2 * i + 2
+            };
+        }
+
+        Ok(self.nodes[i].data)
+    }
+
+    /**
+     * Replaces the element x at position pos with f(x) and returns the new value.
+     */
+    pub fn update_with<F: FnOnce(M) -> M>(
+        &mut self,
+        pos: usize,
+        f: F,
+    ) -> Result<M, RangeError<usize>> {
+        self.check_position(pos)?;
+
+        let mut new_val = M::identity();
+        self.point_update(0, pos, |data| {
+            new_val = f(data);
+            new_val
+        });
+
+        Ok(new_val)
+    }
+
+    /**
+     * Returns the smallest position i >= start such that pred applied to the
+     *  combination of start..=i holds, or None if there's none.
+     *
+     * pred must be monotone: once it holds for a range, it must hold for
+     *  every range extending it to the right.
+     */
+    pub fn find_first<F: FnMut(M) -> bool>(
+        &mut self,
+        start: usize,
+        mut pred: F,
+    ) -> Result<Option<usize>, RangeError<usize>> {
+        self.check_position(start)?;
+
+        let mut acc = M::identity();
+        Ok(self.find_first_node(0, start, &mut pred, &mut acc))
+    }
+
+    /**
+     * Returns the largest position i <= end such that pred applied to the
+     *  combination of i..=end holds, or None if there's none.
+     *
+     * pred must be monotone: once it holds for a range, it must hold for
+     *  every range extending it to the left.
+     */
+    pub fn find_last<F: FnMut(M) -> bool>(
+        &mut self,
+        end: usize,
+        mut pred: F,
+    ) -> Result<Option<usize>, RangeError<usize>> {
+        self.check_position(end)?;
+
+        let mut acc = M::identity();
+        Ok(self.find_last_node(0, end, &mut pred, &mut acc))
+    }
+
+    /**
+     * Appends val to the end of the array.
+     */
+    pub fn push(&mut self, val: M) {
+        if self.len == self.capacity() {
+            self.grow();
+        }
+
+        self.len += 1;
+        self.point_update(0, self.len - 1, |_| val);
+    }
+
+    /**
+     * Removes the last element of the array and returns it, or None if it's empty.
+     *  The capacity is left unchanged.
+     */
+    pub fn pop(&mut self) -> Option<M> {
+        if self.is_empty() {
+            return None;
+        }
+
+        // Every update pending above the leaf is pushed down on the way,
+        // so no pending update is left on a node holding padding
+        let mut val = M::identity();
+        self.point_update(0, self.len - 1, |data| {
+            val = data;
+            M::identity()
+        });
+        self.len -= 1;
+
+        Some(val)
+    }
+
+    /**
+     * Returns all the elements, with every pending update applied.
+     */
+    pub fn get_leaves(&mut self) -> Vec<M> {
+        let mut out = Vec::with_capacity(self.len);
+        if !self.is_empty() {
+            self.collect_leaves(0, &mut out);
+        }
+
+        out
+    }
+
+    /**
+     * Returns a view of every node holding at least one element, in preorder.
+     */
+    pub fn node_views(&self) -> Vec<NodeView<M, T>> {
+        let mut out = Vec::new();
+        if !self.is_empty() {
+            self.collect_node_views(0, 0, &mut out);
+        }
+
+        out
+    }
+
+    fn update_node(&mut self, i: usize, start: usize, end: usize, tag: T) {
+        let node = &self.nodes[i];
+        if start > node.range.end || end < node.range.start || tag.is_noop(&node.data) {
+            // No overlap, or no element is affected
+            return;
+        }
+
+        let is_leaf = node.range.start == node.range.end;
+        if start <= node.range.start
+            && end >= node.range.end
+            && (is_leaf || tag.can_apply(&node.data))
+        {
+            // Total overlap and the update follows from the node's data:
+            // stop here and leave the update pending
+            self.apply_tag(i, tag);
+            return;
+        }
+
+        // Partial overlap, or the update has to be broken down
+        self.propagate_pending_update(i);
+        self.update_node(2 * i + 1, start, end, tag);
+        self.update_node(2 * i + 2, start, end, tag);
+
+        self.nodes[i].data = M::combine(self.nodes[2 * i + 1].data, self.nodes[2 * i + 2].data);
+    }
+
+    fn query_node(&mut self, i: usize, start: usize, end: usize) -> M {
+        let range = &self.nodes[i].range;
+        if start > range.end || end < range.start {
+            // No overlap
+            return M::identity();
+        }
+
+        if start <= range.start && end >= range.end {
+            // Total overlap
+            return self.nodes[i].data;
+        }
+
+        // Partial overlap
+        self.propagate_pending_update(i);
+        let left = self.query_node(2 * i + 1, start, end);
+        let right = self.query_node(2 * i + 2, start, end);
+
+        M::combine(left, right)
+    }
+
+    /**
+     * Recursively looks for the smallest position i >= start in node i's subtree
+     *  such that pred(combination of start..=i) holds, acc holding the combination
+     *  of the elements from start to the beginning of the node's range.
+     */
+    fn find_first_node<F: FnMut(M) -> bool>(
+        &mut self,
+        i: usize,
+        start: usize,
+        pred: &mut F,
+        acc: &mut M,
+    ) -> Option<usize> {
+        if start > self.nodes[i].range.end {
+            // No overlap
+            return None;
+        }
+
+        if start <= self.nodes[i].range.start {
+            // Total overlap: the answer is in this node only if pred holds for its whole range
+            let combined = M::combine(*acc, self.nodes[i].data);
+            if !pred(combined) {
+                *acc = combined;
+                return None;
+            }
+
+            if self.nodes[i].range.start == self.nodes[i].range.end {
+                return Some(self.nodes[i].range.start);
+            }
+        }
+
+        // Partial overlap, or the answer is somewhere in this node: descend
+        self.propagate_pending_update(i);
+
+        self.find_first_node(2 * i + 1, start, pred, acc)
+            .or_else(|| self.find_first_node(2 * i + 2, start, pred, acc))
+    }
+
+    /**
+     * Recursively looks for the largest position i <= end in node i's subtree
+     *  such that pred(combination of i..=end) holds, acc holding the combination
+     *  of the elements from the end of the node's range to end.
+     */
+    fn find_last_node<F: FnMut(M) -> bool>(
+        &mut self,
+        i: usize,
+        end: usize,
+        pred: &mut F,
+        acc: &mut M,
+    ) -> Option<usize> {
+        if end < self.nodes[i].range.start {
+            // No overlap
+            return None;
+        }
+
+        if end >= self.nodes[i].range.end {
+            // Total overlap: the answer is in this node only if pred holds for its whole range
+            let combined = M::combine(self.nodes[i].data, *acc);
+            if !pred(combined) {
+                *acc = combined;
+                return None;
+            }
+
+            if self.nodes[i].range.start == self.nodes[i].range.end {
+                return Some(self.nodes[i].range.start);
+            }
+        }
+
+        // Partial overlap, or the answer is somewhere in this node: descend
+        self.propagate_pending_update(i);
+
+        self.find_last_node(2 * i + 2, end, pred, acc)
+            .or_else(|| self.find_last_node(2 * i + 1, end, pred, acc))
+    }
+
+    fn collect_leaves(&mut self, i: usize, out: &mut Vec<M>) {
+        if self.nodes[i].range.start >= self.len {
+            // Padding
+            return;
+        }
+
+        if self.nodes[i].range.start == self.nodes[i].range.end {
+            out.push(self.nodes[i].data);
+            return;
+        }
+
+        self.propagate_pending_update(i);
+        self.collect_leaves(2 * i + 1, out);
+        self.collect_leaves(2 * i + 2, out);
+    }
+
+    /**
+     * Pushes the views of the nodes in node i's subtree, skipping
+     *  the ones only made of padding, in the out vector.
+     */
+    fn collect_node_views(&self, i: usize, depth: usize, out: &mut Vec<NodeView<M, T>>) {
+        let node = &self.nodes[i];
+        if node.range.start >= self.len {
+            // Padding
+            return;
+        }
+
+        out.push(NodeView {
+            index: i,
+            depth,
+            start: node.range.start,
+            end: node.range.end,
+            padding: (node.range.end + 1).saturating_sub(self.len),
+            data: node.data,
+            pending: node.pending,
+        });
+
+        if node.range.start != node.range.end {
+            self.collect_node_views(2 * i + 1, depth + 1, out);
+            self.collect_node_views(2 * i + 2, depth + 1, out);
+        }
+    }
+
+    /**
+     * Recursively replaces the leaf x at position pos with f(x),
+     *  bringing every node on the path up to date.
+     */
+    fn point_update<F: FnOnce(M) -> M>(&mut self, i: usize, pos: usize, f: F) {
+        // Leaf
+        if self.nodes[i].range.start == self.nodes[i].range.end {
+            self.nodes[i].data = f(self.nodes[i].data);
+            return;
+        }
+
+        self.propagate_pending_update(i);
+
+        let left_i = 2 * i + 1;
+        let right_i = 2 * i + 2;
+        if pos <= self.nodes[left_i].range.end {
+            self.point_update(left_i, pos, f);
+        } else {
+            self.point_update(right_i, pos, f);
+        }
+
+        self.nodes[i].data = M::combine(self.nodes[left_i].data, self.nodes[right_i].data);
+    }
+
+    /**
+     * Returns an error if pos is not a valid position in the array.
+     */
+    fn check_position(&self, pos: usize) -> Result<(), RangeError<usize>> {
+        if pos >= self.len {
+            return Err(RangeError::OutOfBounds {
+                index: pos,
+                len: self.len,
+            });
+        }
+
+        Ok(())
+    }
+
+    /**
+     * Doubles the tree's capacity: the current tree becomes the left subtree
+     *  of the new root, pending updates included, and the right one is padding.
+     */
+    fn grow(&mut self) {
+        let capacity = self.capacity();
+        let mut nodes: Vec<Node<M, T>> = vec![SegmentTree::padding_node(); 4 * capacity - 1];
+
+        // The j-th node on level d moves to the j-th position of level d + 1
+        let mut level_size = 1;
+        while level_size <= capacity {
+            let old_start = level_size - 1;
+            let new_start = 2 * level_size - 1;
+            nodes[new_start..new_start + level_size]
+                .clone_from_slice(&self.nodes[old_start..old_start + level_size]);
+            level_size *= 2;
+        }
+
+        let new_end = 2 * capacity - 1;
+        SegmentTree::build_tree(&mut nodes, 2, &[], capacity, new_end);
+        nodes[0] = Node {
+            data: M::combine(nodes[1].data, nodes[2].data),
+            range: Range {
+                start: 0,
+                end: new_end,
+            },
+            pending: None,
+        };
+
+        self.nodes = nodes;
+    }
+
+    fn padding_node() -> Node<M, T> {
+        Node {
+            data: M::identity(),
+            range: Range { start: 0, end: 0 },
+            pending: None,
+        }
+    }
+
+    /**
+     * Recursively build the segment tree, the leaves past the end of data being padding
+     */
+    fn build_tree(
+        nodes: &mut Vec<Node<M, T>>,
+        i: usize,
+        data: &[M],
+        start: usize,
+        end: usize,
+    ) -> M {
+        // Leaf
+        if start == end {
+            nodes[i] = Node {
+                data: data.get(start).copied().unwrap_or_else(M::identity),
+                range: Range { start, end },
+                pending: None,
+            };
+
+            return nodes[i].data;
+        }
+
+        // Non-leaf node
+        let m = start + (end - start) / 2;
+        let left = SegmentTree::build_tree(nodes, 2 * i + 1, data, start, m);
+        let right = SegmentTree::build_tree(nodes, 2 * i + 2, data, m + 1, end);
+
+        nodes[i] = Node {
+            data: M::combine(left, right),
+            range: Range { start, end },
+            pending: None,
+        };
+
+        nodes[i].data
+    }
+
+    /**
+     * Applies tag to the whole range of node i, recording it
+     *  as pending for the node's children.
+     */
+    fn apply_tag(&mut self, i: usize, tag: T) {
+        let node = &mut self.nodes[i];
+        node.data = tag.apply(node.data);
+
+        if node.range.start != node.range.end {
+            node.pending = Some(match node.pending {
+                Some(old_tag) => tag.compose(old_tag),
+                None => tag,
+            });
+        }
+    }
+
+    /**
+     * Pushes any pending update on node i to the node's children.
+     *  The update could be applied to the node's whole range, so
+     *  it can be applied to each child's too (see Tag::can_apply).
+     */
+    fn propagate_pending_update(&mut self, i: usize) {
+        if let Some(tag) = self.nodes[i].pending.take() {
+            self.apply_tag(2 * i + 1, tag);
+            self.apply_tag(2 * i + 2, tag);
+        }
+    }
+}
+
+impl<M: Monoid + PartialEq + fmt::Debug, T: Tag<M> + fmt::Display> SegmentTree<M, T> {
+    /**
+     * Returns the tree in Graphviz DOT format, with every node's range,
+     *  data (as written by describe) and pending update, nodes only made
+     *  of padding excluded.
+     */
+    pub fn to_dot<F: Fn(&M) -> String>(&self, describe: F) -> String {
+        let mut dot = String::from("digraph SegmentTree {\n    node [shape=record];\n");
+        for view in self.node_views() {
+            dot += &format!(
+                "    n{} [label=\"[{}, {}]{} | {} | pending: {}\"];\n",
+                view.index,
+                view.start,
+                view.end,
+                view.describe_padding(),
+                describe(&view.data),
+                view.describe_pending()
+            );
+            if view.index > 0 {
+                dot += &format!("    n{} -> n{};\n", (view.index - 1) / 2, view.index);
+            }
+        }
+        dot += "}\n";
+
+        dot
+    }
+
+    /**
+     * Same as validate_with, with no check on the elements themselves.
+     */
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        self.validate_with(|_| Ok(()))
+    }
+
+    /**
+     * Walks the whole tree checking its invariants: every node covers the
+     *  expected half of its parent's range, padding leaves hold the identity,
+     *  leaves pass check_element and every internal node's data matches its
+     *  children's, once its pending update is applied to them.
+     * Returns the first violation found, children being checked before
+     *  their parent so that the deepest offending node is reported.
+     */
+    pub fn validate_with<F: Fn(&M) -> Result<(), String>>(
+        &self,
+        check_element: F,
+    ) -> Result<(), ValidationError> {
+        let capacity = self.capacity();
+        if !capacity.is_power_of_two() || self.nodes.len() != 2 * capacity - 1 {
+            return Err(self.violation(
+                0,
+                format!(
+                    "{} nodes do not form a perfect binary tree",
+                    self.nodes.len()
+                ),
+            ));
+        }
+        if self.len > capacity {
+            return Err(self.violation(
+                0,
+                format!("length {} exceeds the capacity {}", self.len, capacity),
+            ));
+        }
+
+        self.validate_node(0, 0, capacity - 1, &check_element)
+    }
+
+    /**
+     * Recursively checks the invariants of node i's subtree,
+     *  which should cover the <start, end> (inclusive) range.
+     */
+    fn validate_node<F: Fn(&M) -> Result<(), String>>(
+        &self,
+        i: usize,
+        start: usize,
+        end: usize,
+        check_element: &F,
+    ) -> Result<(), ValidationError> {
+        let node = &self.nodes[i];
+        if node.range.start != start || node.range.end != end {
+            return Err(self.violation(i, format!("expected range [{}, {}]", start, end)));
+        }
+
+        // Padding only ever holds the identity, updates never reach it
+        if start >= self.len {
+            if node.data != M::identity() {
+                return Err(self.violation(i, format!("padding holds {:?}", node.data)));
+            }
+            if let Some(pending) = node.pending {
+                return Err(self.violation(i, format!("padding has pending {}", pending)));
+            }
+        }
+
+        // Leaf
+        if start == end {
+            if let Some(pending) = node.pending {
+                return Err(self.violation(i, format!("leaf has pending {}", pending)));
+            }
+            if start < self.len {
+                check_element(&node.data).map_err(|reason| self.violation(i, reason))?;
+            }
+
+            return Ok(());
+        }
+
+        let m = start + (end - start) / 2;
+        self.validate_node(2 * i + 1, start, m, check_element)?;
+        self.validate_node(2 * i + 2, m + 1, end, check_element)?;
+
+        // Bring the children up to date with the node's pending update
+        let [left, right] = [2 * i + 1, 2 * i + 2].map(|child| match node.pending {
+            Some(tag) => tag.apply(self.nodes[child].data),
+            None => self.nodes[child].data,
+        });
+
+        let expected = M::combine(left, right);
+        if node.data != expected {
+            return Err(self.violation(
+                i,
+                format!(
+                    "holds {:?} but its children combine to {:?}",
+                    node.data, expected
+                ),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /**
+     * Returns a ValidationError about node i.
+     */
+    fn violation(&self, i: usize, reason: String) -> ValidationError {
+        ValidationError {
+            node: i,
+            start: self.nodes[i].range.start,
+            end: self.nodes[i].range.end,
+            reason,
+        }
+    }
+}
+
+/**
+ * Minimum and maximum of a range.
+ */
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MinMax {
+    pub min: i32,
+    pub max: i32,
+}
+
+impl MinMax {
+    pub fn new(val: i32) -> Self {
+        Self { min: val, max: val }
+    }
+}
+
+impl Monoid for MinMax {
+    fn identity() -> Self {
+        Self {
+            min: i32::MAX,
+            max: i32::MIN,
+        }
+    }
+
+    fn combine(a: Self, b: Self) -> Self {
+        Self {
+            min: cmp::min(a.min, b.min),
+            max: cmp::max(a.max, b.max),
+        }
+    }
+}
+
+/**
+ * Substitutes every element x with min(x, val).
+ */
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Chmin(pub i32);
+
+impl Tag<MinMax> for Chmin {
+    fn apply(&self, data: MinMax) -> MinMax {
+        // The identity must stay such, or empty ranges would get a value
+        if data == MinMax::identity() {
+            return data;
+        }
+
+        MinMax {
+            min: cmp::min(data.min, self.0),
+            max: cmp::min(data.max, self.0),
+        }
+    }
+
+    fn compose(&self, older: Self) -> Self {
+        Chmin(cmp::min(self.0, older.0))
+    }
+
+    fn is_noop(&self, data: &MinMax) -> bool {
+        data.max <= self.0
+    }
+}
+
+impl fmt::Display for Chmin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "chmin {}", self.0)
+    }
+}
+
+/**
+ * Sum of a range, along with the range's extremes, which tell whether
+ *  an Add makes any of its elements overflow, and the range's length.
+ */
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Sum {
+    pub sum: i128, // Wide enough for the sum of any number of i64 elements
+    pub min: i64,
+    pub max: i64,
+    pub len: usize,
+}
+
+impl Sum {
+    pub fn new(val: i64) -> Self {
+        Self {
+            sum: val as i128,
+            min: val,
+            max: val,
+            len: 1,
+        }
+    }
+}
+
+impl Monoid for Sum {
+    fn identity() -> Self {
+        Self {
+            sum: 0,
+            min: i64::MAX,
+            max: i64::MIN,
+            len: 0,
+        }
+    }
+
+    fn combine(a: Self, b: Self) -> Self {
+        Self {
+            sum: a.sum + b.sum,
+            min: cmp::min(a.min, b.min),
+            max: cmp::max(a.max, b.max),
+            len: a.len + b.len,
+        }
+    }
+}
+
+/**
+ * Increases every element by val, elements that overflow an i64
+ *  wrapping around. val is an i128 so that pending additions can be
+ *  composed without overflowing.
+ * Ranges with an element that wraps are broken down to single elements,
+ *  so each wrapped element costs an extra O(log n) time.
+ */
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Add(pub i128);
+
+impl Add {
+    /**
+     * Returns whether the update makes any of the elements
+     *  of a range with combined value data overflow.
+     */
+    pub fn overflows(&self, data: Sum) -> bool {
+        data.len > 0
+            && (data.min as i128 + self.0 < i64::MIN as i128
+                || data.max as i128 + self.0 > i64::MAX as i128)
+    }
+}
+
+impl Tag<Sum> for Add {
+    fn apply(&self, data: Sum) -> Sum {
+        match data.len {
+            // The identity must stay such, or empty ranges would get a value
+            0 => data,
+            // A single element wraps around like i64 arithmetic
+            1 => Sum::new((data.min as i128 + self.0) as i64),
+            // No element overflows, see can_apply
+            len => Sum {
+                sum: data.sum + self.0 * len as i128,
+                min: (data.min as i128 + self.0) as i64,
+                max: (data.max as i128 + self.0) as i64,
+                len,
+            },
+        }
+    }
+
+    fn compose(&self, older: Self) -> Self {
+        Add(self.0 + older.0)
+    }
+
+    fn can_apply(&self, data: &Sum) -> bool {
+        // A wrapped element's new value doesn't follow from the range's extremes
+        !self.overflows(*data)
+    }
+}
+
+impl fmt::Display for Add {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "add {}", self.0)
+    }
+}
+
+/**
+ * Range max/min queries with range chmin updates (hands-on 02, problem 1).
+ */
+pub type MinMaxTree = SegmentTree<MinMax, Chmin>;
+
+/**
+ * Range sum queries with range add updates (hands-on 02, problem 2).
+ */
+pub type RangeAddTree = SegmentTree<Sum, Add>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn test_min_max_tree() {
+        let mut rng = rand::thread_rng();
+        let mut truth: Vec<i32> = (0..100).map(|_| rng.gen_range(0..1000)).collect();
+        let leaves: Vec<MinMax> = truth.iter().map(|&x| MinMax::new(x)).collect();
+        let mut tree = MinMaxTree::new(&leaves);
+
+        for _ in 0..1000 {
+            let a = rng.gen_range(0..truth.len());
+            let b = rng.gen_range(0..truth.len());
+            let (start, end) = (cmp::min(a, b), cmp::max(a, b));
+
+            if rng.gen_bool(0.5) {
+                let val = rng.gen_range(0..1000);
+                tree.range_update(start..=end, Chmin(val)).unwrap();
+                for x in &mut truth[start..=end] {
+                    *x = cmp::min(*x, val);
+                }
+            } else {
+                let result = tree.range_query(start..=end).unwrap();
+                assert_eq!(result.min, *truth[start..=end].iter().min().unwrap());
+                assert_eq!(result.max, *truth[start..=end].iter().max().unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn test_range_add_tree() {
+        let mut rng = rand::thread_rng();
+        let mut truth: Vec<i64> = (0..100).map(|_| rng.gen_range(-1000..1000)).collect();
+        let leaves: Vec<Sum> = truth.iter().map(|&x| Sum::new(x)).collect();
+        let mut tree = RangeAddTree::new(&leaves);
+
+        for _ in 0..1000 {
+            let a = rng.gen_range(0..truth.len());
+            let b = rng.gen_range(0..truth.len());
+            let (start, end) = (cmp::min(a, b), cmp::max(a, b));
+
+            if rng.gen_bool(0.5) {
+                let val: i64 = rng.gen_range(-1000..1000);
+                tree.range_update(start..=end, Add(val as i128)).unwrap();
+                for x in &mut truth[start..=end] {
+                    *x += val;
+                }
+            } else {
+                let result = tree.range_query(start..=end).unwrap();
+                assert_eq!(result.sum, truth[start..=end].iter().sum::<i64>() as i128);
+            }
+        }
+
+        let leaves: Vec<i64> = tree.get_leaves().iter().map(|leaf| leaf.min).collect();
+        assert_eq!(leaves, truth);
+    }
+
+    #[test]
+    fn test_wrapping_add() {
+        let mut rng = rand::thread_rng();
+        let mut truth: Vec<i64> = (0..50).map(|_| rng.gen()).collect();
+        let leaves: Vec<Sum> = truth.iter().map(|&x| Sum::new(x)).collect();
+        let mut tree = RangeAddTree::new(&leaves);
+
+        // Random additions near the limits, against a wrapping array
+        for _ in 0..500 {
+            let a = rng.gen_range(0..truth.len());
+            let b = rng.gen_range(0..truth.len());
+            let (start, end) = (cmp::min(a, b), cmp::max(a, b));
+
+            let val: i64 = rng.gen();
+            tree.range_update(start..=end, Add(val as i128)).unwrap();
+            for x in &mut truth[start..=end] {
+                *x = x.wrapping_add(val);
+            }
+            assert_eq!(tree.validate(), Ok(()));
+
+            // Sums, minimums and maximums are exact on the wrapped elements
+            let expected = truth[start..=end]
+                .iter()
+                .fold(Sum::identity(), |acc, &x| Sum::combine(acc, Sum::new(x)));
+            assert_eq!(tree.range_query(start..=end), Ok(expected));
+        }
+
+        let leaves: Vec<i64> = tree.get_leaves().iter().map(|leaf| leaf.min).collect();
+        assert_eq!(leaves, truth);
+
+        let data = Sum::combine(Sum::new(i64::MAX - 1), Sum::new(0));
+        assert!(!Add(1).overflows(data));
+        assert!(Add(2).overflows(data));
+        assert!(!Add(i64::MIN as i128).overflows(data));
+        assert!(Add(i64::MIN as i128 - 1).overflows(data));
+        assert!(!Add(i128::MAX).overflows(Sum::identity()));
+    }
+
+    #[test]
+    fn test_break_down() {
+        /**
+         * Adds the value to every element, but only lets the tree apply
+         *  it to ranges whose elements are all equal.
+         */
+        #[derive(Copy, Clone, Debug)]
+        struct Shift(i32);
+
+        impl Tag<MinMax> for Shift {
+            fn apply(&self, data: MinMax) -> MinMax {
+                if data == MinMax::identity() {
+                    return data;
+                }
+
+                MinMax {
+                    min: data.min + self.0,
+                    max: data.max + self.0,
+                }
+            }
+
+            fn compose(&self, older: Self) -> Self {
+                Shift(self.0 + older.0)
+            }
+
+            fn is_noop(&self, _data: &MinMax) -> bool {
+                self.0 == 0
+            }
+
+            fn can_apply(&self, data: &MinMax) -> bool {
+                data.min == data.max
+            }
+        }
+
+        impl fmt::Display for Shift {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "shift {}", self.0)
+            }
+        }
+
+        let leaves: Vec<MinMax> = [4, 4, 4, 4, 1, 2].iter().map(|&x| MinMax::new(x)).collect();
+        let mut tree: SegmentTree<MinMax, Shift> = SegmentTree::new(&leaves);
+        tree.range_update(.., Shift(3)).unwrap();
+        assert_eq!(tree.validate(), Ok(()));
+
+        // The uniform node [0, 3] keeps the update pending, [4, 5] is broken down
+        let pending: Vec<(usize, String)> = tree
+            .node_views()
+            .iter()
+            .filter(|view| view.pending.is_some())
+            .map(|view| (view.index, view.describe_pending()))
+            .collect();
+        assert_eq!(pending, vec![(1, String::from("shift 3"))]);
+
+        let leaves: Vec<i32> = tree.get_leaves().iter().map(|leaf| leaf.max).collect();
+        assert_eq!(leaves, vec![7, 7, 7, 7, 4, 5]);
+    }
+
+    #[test]
+    fn test_point_operations() {
+        let mut rng = rand::thread_rng();
+        let mut truth: Vec<i32> = Vec::new();
+        let mut tree = MinMaxTree::new(&[]);
+
+        for _ in 0..2000 {
+            let val = rng.gen_range(-100..100);
+            match rng.gen_range(0..6) {
+                0 | 1 => {
+                    tree.push(MinMax::new(val));
+                    truth.push(val);
+                }
+                2 => assert_eq!(tree.pop(), truth.pop().map(MinMax::new)),
+                _ if truth.is_empty() => {}
+                3 => {
+                    // Leave an update pending on the elements that are going to be popped
+                    let start = rng.gen_range(0..truth.len());
+                    tree.range_update(start.., Chmin(val)).unwrap();
+                    for x in &mut truth[start..] {
+                        *x = cmp::min(*x, val);
+                    }
+                }
+                4 => {
+                    let pos = rng.gen_range(0..truth.len());
+                    assert_eq!(
+                        tree.update_with(pos, |_| MinMax::new(val)),
+                        Ok(MinMax::new(val))
+                    );
+                    truth[pos] = val;
+                }
+                _ => {
+                    let pos = rng.gen_range(0..truth.len());
+                    assert_eq!(tree.get(pos), Ok(MinMax::new(truth[pos])));
+
+                    let expected = (pos..truth.len()).find(|&i| truth[i] > val);
+                    assert_eq!(tree.find_first(pos, |data| data.max > val), Ok(expected));
+                    let expected = (0..=pos).rev().find(|&i| truth[i] > val);
+                    assert_eq!(tree.find_last(pos, |data| data.max > val), Ok(expected));
+                }
+            }
+
+            assert_eq!(tree.len(), truth.len());
+            assert!(tree.capacity() >= truth.len());
+            assert_eq!(tree.validate(), Ok(()));
+        }
+
+        let leaves: Vec<i32> = tree.get_leaves().iter().map(|leaf| leaf.max).collect();
+        assert_eq!(leaves, truth);
+        assert_eq!(
+            tree.get(truth.len()),
+            Err(RangeError::OutOfBounds {
+                index: truth.len(),
+                len: truth.len()
+            })
+        );
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let leaves: Vec<Sum> = [5, 1, 9].iter().map(|&x| Sum::new(x)).collect();
+        let mut tree = RangeAddTree::new(&leaves);
+        tree.range_update(..2, Add(2)).unwrap();
+
+        let dot = tree.to_dot(|data| format!("sum {}", data.sum));
+        assert!(dot.starts_with("digraph SegmentTree {"));
+        assert!(dot.contains("n0 [label=\"[0, 3] (1 padding) | sum 19 | pending: none\"];"));
+        assert!(dot.contains("n1 [label=\"[0, 1] | sum 10 | pending: add 2\"];"));
+        assert!(dot.contains("n0 -> n2;"));
+        // The leaf at position 3 is padding
+        assert!(!dot.contains("n6"));
+    }
+
+    #[test]
+    fn test_validate() {
+        let leaves: Vec<MinMax> = [3, 1, 4, 1, 5].iter().map(|&x| MinMax::new(x)).collect();
+        let mut tree = MinMaxTree::new(&leaves);
+        tree.range_update(.., Chmin(3)).unwrap();
+        assert_eq!(tree.validate(), Ok(()));
+
+        // A leaf no longer matching its parent
+        let mut corrupted = tree.clone();
+        corrupted.nodes[8].data = MinMax::new(2);
+        let error = corrupted.validate().unwrap_err();
+        assert_eq!((error.node, error.start, error.end), (3, 0, 1));
+
+        // Padding must stay untouched
+        let mut corrupted = tree.clone();
+        corrupted.nodes[13].data = MinMax::new(0);
+        assert_eq!(
+            corrupted.validate().unwrap_err().to_string(),
+            format!("node 13 ([6, 6]): padding holds {:?}", MinMax::new(0))
+        );
+
+        // Node 1 covers [0, 1] and keeps the update pending on its children
+        let leaves: Vec<Sum> = [5, 1, 9].iter().map(|&x| Sum::new(x)).collect();
+        let mut tree = RangeAddTree::new(&leaves);
+        tree.range_update(..2, Add(3)).unwrap();
+        assert_eq!(tree.validate(), Ok(()));
+
+        let mut corrupted = tree.clone();
+        corrupted.nodes[1].pending = None;
+        let error = corrupted.validate().unwrap_err();
+        assert_eq!((error.node, error.start, error.end), (1, 0, 1));
+
+        let mut corrupted = tree.clone();
+        corrupted.nodes[4].pending = Some(Add(1));
+        assert_eq!(
+            corrupted.validate().unwrap_err().to_string(),
+            "node 4 ([1, 1]): leaf has pending add 1"
+        );
+
+        // Elements are checked by the caller, below pending updates too
+        let error = tree
+            .validate_with(|data| match data.sum {
+                1 => Err(String::from("holds 1")),
+                _ => Ok(()),
+            })
+            .unwrap_err();
+        assert_eq!(error.to_string(), "node 4 ([1, 1]): holds 1");
+    }
+
+    #[test]
+    fn test_to_bounds() {
+        assert_eq!(to_bounds(.., 5usize), Ok((0, 5)));
+        assert_eq!(to_bounds(1..=3, 5usize), Ok((1, 4)));
+        assert_eq!(to_bounds(2..2, 5usize), Ok((2, 2)));
+        assert_eq!(to_bounds(..=u64::MAX - 1, u64::MAX), Ok((0, u64::MAX)));
+        let (start, end) = (3, 1);
+        assert_eq!(
+            to_bounds(start..end, 5usize),
+            Err(RangeError::Reversed { start: 3, end: 1 })
+        );
+        assert_eq!(
+            to_bounds(2..=5, 5usize),
+            Err(RangeError::OutOfBounds { index: 5, len: 5 })
+        );
+
+        // The last position of usize is past the end of any array
+        assert_eq!(
+            to_bounds(..=usize::MAX, usize::MAX),
+            Err(RangeError::OutOfBounds {
+                index: usize::MAX,
+                len: usize::MAX
+            })
+        );
+        assert_eq!(
+            to_bounds((Bound::Excluded(u64::MAX), Bound::Unbounded), u64::MAX),
+            Err(RangeError::OutOfBounds {
+                index: u64::MAX,
+                len: u64::MAX
+            })
+        );
+        assert_eq!(to_bounds(..usize::MAX, usize::MAX), Ok((0, usize::MAX)));
+
+        assert_eq!(to_inclusive(1..3, 5u64), Ok(Some((1, 2))));
+        assert_eq!(to_inclusive(3..3, 5u64), Ok(None));
+    }
+
+    #[test]
+    fn test_range_errors() {
+        let leaves: Vec<Sum> = [3, 1, 4].iter().map(|&x| Sum::new(x)).collect();
+        let mut tree = RangeAddTree::new(&leaves);
+
+        assert_eq!(
+            tree.range_update(1..=3, Add(1)),
+            Err(RangeError::OutOfBounds { index: 3, len: 3 })
+        );
+        let (start, end) = (2, 1);
+        assert_eq!(
+            tree.range_query(start..end),
+            Err(RangeError::Reversed { start: 2, end: 1 })
+        );
+        assert_eq!(tree.range_query(1..1), Ok(Sum::identity()));
+        assert_eq!(tree.range_query(..).map(|data| data.sum), Ok(8));
+    }
+
+    #[test]
+    fn test_empty_tree() {
+        let mut tree = RangeAddTree::new(&[]);
+        assert!(tree.is_empty());
+        assert!(tree.get_leaves().is_empty());
+        assert_eq!(tree.range_query(..), Ok(Sum::identity()));
+    }
+}
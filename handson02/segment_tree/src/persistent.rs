@@ -0,0 +1,401 @@
+use std::ops::RangeBounds;
+
+use crate::{to_inclusive, Monoid, RangeError, Tag};
+
+#[derive(Clone, Debug)]
+struct Node<M, T> {
+    data: M,            // Combined value of the node's range, own pending update included
+    pending: Option<T>, // Update applied to the whole range but not to the node's children
+    left: usize,        // Index of the left child, unused for leaves
+    right: usize,       // Index of the right child, unused for leaves
+}
+
+/**
+ * Segment tree that keeps every past version of the array.
+ *
+ * Updates never modify existing nodes: they copy the O(log n) nodes on the
+ *  paths they visit and share the rest with the previous version.
+ * Updates push pending updates down to copies of the children before going
+ *  past a node, so a node's pending update is always newer than the ones in
+ *  its subtree. Queries, which can't copy nodes, apply it to their results
+ *  on the way back up instead, which is equivalent.
+ */
+#[derive(Debug)]
+pub struct PersistentSegmentTree<M, T> {
+    nodes: Vec<Node<M, T>>,
+    roots: Vec<usize>, // Root of each version
+    len: usize,
+}
+
+impl<M: Monoid, T: Tag<M>> PersistentSegmentTree<M, T> {
+    /**
+     * Builds a segment tree on the provided data and returns it.
+     *  The tree starts with a single version, 0, holding data.
+     */
+    pub fn new(data: &[M]) -> Self {
+        let mut tree = Self {
+            nodes: Vec::with_capacity(2 * data.len()),
+            roots: Vec::new(),
+            len: data.len(),
+        };
+
+        if !data.is_empty() {
+            let root = tree.build_tree(data, 0, data.len() - 1);
+            tree.roots.push(root);
+        } else {
+            tree.roots.push(usize::MAX);
+        }
+
+        tree
+    }
+
+    /**
+     * Returns the number of elements in the tree.
+     */
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /**
+     * Returns the number of versions, the initial one included.
+     */
+    pub fn version_count(&self) -> usize {
+        self.roots.len()
+    }
+
+    /**
+     * Applies tag to the elements in range of the latest version,
+     *  creating a new version, whose number is returned.
+     */
+    pub fn range_update<R: RangeBounds<usize>>(
+        &mut self,
+        range: R,
+        tag: T,
+    ) -> Result<usize, RangeError<usize>> {
+        let root = *self.roots.last().unwrap();
+        let new_root = match to_inclusive(range, self.len)? {
+            Some((start, end)) => self.update_node(root, 0, self.len - 1, start, end, tag),
+            None => root,
+        };
+
+        self.roots.push(new_root);
+        Ok(self.roots.len() - 1)
+    }
+
+    /**
+     * Returns the combination of the elements in range of the latest version.
+     */
+    pub fn range_query<R: RangeBounds<usize>>(&self, range: R) -> Result<M, RangeError<usize>> {
+        self.query_at(self.roots.len() - 1, range)
+    }
+
+    /**
+     * Returns the combination of the elements in range as they were
+     *  right after the update that created version.
+     *  A version that doesn't exist yet is reported as out of bounds
+     *  of the version count.
+     */
+    pub fn query_at<R: RangeBounds<usize>>(
+        &self,
+        version: usize,
+        range: R,
+    ) -> Result<M, RangeError<usize>> {
+        self.check_version(version)?;
+
+        match to_inclusive(range, self.len)? {
+            Some((start, end)) => {
+                Ok(self.query_node(self.roots[version], 0, self.len - 1, start, end))
+            }
+            None => Ok(M::identity()),
+        }
+    }
+
+    /**
+     * Returns all the elements of version.
+     */
+    pub fn leaves_at(&self, version: usize) -> Result<Vec<M>, RangeError<usize>> {
+        self.check_version(version)?;
+
+        (0..self.len)
+            .map(|i| self.query_at(version, i..=i))
+            .collect()
+    }
+
+    fn check_version(&self, version: usize) -> Result<(), RangeError<usize>> {
+        if version >= self.roots.len() {
+            return Err(RangeError::OutOfBounds {
+                index: version,
+                len: self.roots.len(),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn build_tree(&mut self, data: &[M], lo: usize, hi: usize) -> usize {
+        // Leaf
+        if lo == hi {
+            return self.push_node(data[lo], None, 0, 0);
+        }
+
+        // Non-leaf node
+        let m = lo + (hi - lo) / 2;
+        let left = self.build_tree(data, lo, m);
+        let right = self.build_tree(data, m + 1, hi);
+        let data = M::combine(self.nodes[left].data, self.nodes[right].data);
+
+        self.push_node(data, None, left, right)
+    }
+
+    /**
+     * Returns a copy of node i, covering <lo, hi>, with tag applied
+     *  to the elements of the <start, end> range.
+     */
+    fn update_node(
+        &mut self,
+        i: usize,
+        lo: usize,
+        hi: usize,
+        start: usize,
+        end: usize,
+        tag: T,
+    ) -> usize {
+        if start > hi || end < lo || tag.is_noop(&self.nodes[i].data) {
+            // No overlap, or no element is affected: the node can be shared
+            return i;
+        }
+
+        if start <= lo && end >= hi && (lo == hi || tag.can_apply(&self.nodes[i].data)) {
+            // Total overlap and the update follows from the node's data:
+            // stop here and leave the update pending
+            return self.copy_with_tag(i, lo == hi, tag);
+        }
+
+        // Partial overlap, or the update has to be broken down: it must reach the
+        // children after the pending one, so push the latter down to copies of
+        // the children first
+        let node = self.nodes[i].clone();
+        let m = lo + (hi - lo) / 2;
+        let (mut left, mut right) = (node.left, node.right);
+        if let Some(pending) = node.pending {
+            left = self.copy_with_tag(left, lo == m, pending);
+            right = self.copy_with_tag(right, m + 1 == hi, pending);
+        }
+
+        let left = self.update_node(left, lo, m, start, end, tag);
+        let right = self.update_node(right, m + 1, hi, start, end, tag);
+        let data = M::combine(self.nodes[left].data, self.nodes[right].data);
+
+        self.push_node(data, None, left, right)
+    }
+
+    /**
+     * Returns a copy of node i with tag applied to its whole range
+     *  and recorded as pending for its children, unless it's a leaf.
+     */
+    fn copy_with_tag(&mut self, i: usize, is_leaf: bool, tag: T) -> usize {
+        let node = self.nodes[i].clone();
+        let pending = if is_leaf {
+            None
+        } else {
+            Some(match node.pending {
+                Some(old_tag) => tag.compose(old_tag),
+                None => tag,
+            })
+        };
+
+        self.push_node(tag.apply(node.data), pending, node.left, node.right)
+    }
+
+    fn query_node(&self, i: usize, lo: usize, hi: usize, start: usize, end: usize) -> M {
+        if start > hi || end < lo {
+            // No overlap
+            return M::identity();
+        }
+
+        let node = &self.nodes[i];
+        if start <= lo && end >= hi {
+            // Total overlap
+            return node.data;
+        }
+
+        // Partial overlap: the children don't know about this node's pending
+        // update, which is newer than any of theirs
+        let m = lo + (hi - lo) / 2;
+        let combined = M::combine(
+            self.query_node(node.left, lo, m, start, end),
+            self.query_node(node.right, m + 1, hi, start, end),
+        );
+
+        match node.pending {
+            Some(pending) => pending.apply(combined),
+            None => combined,
+        }
+    }
+
+    fn push_node(&mut self, data: M, pending: Option<T>, left: usize, right: usize) -> usize {
+        self.nodes.push(Node {
+            data,
+            pending,
+            left,
+            right,
+        });
+
+        self.nodes.len() - 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Add, Chmin, MinMax, Sum};
+    use rand::Rng;
+    use std::cmp;
+
+    #[test]
+    fn test_versions() {
+        let mut rng = rand::thread_rng();
+        let truth: Vec<i32> = (0..100).map(|_| rng.gen_range(0..1000)).collect();
+        let leaves: Vec<MinMax> = truth.iter().map(|&x| MinMax::new(x)).collect();
+        let mut tree: PersistentSegmentTree<MinMax, Chmin> = PersistentSegmentTree::new(&leaves);
+
+        // Keep a copy of the array after each update
+        let mut snapshots = vec![truth];
+        for _ in 0..200 {
+            let a = rng.gen_range(0..100);
+            let b = rng.gen_range(0..100);
+            let (start, end) = (cmp::min(a, b), cmp::max(a, b));
+            let val = rng.gen_range(0..1000);
+
+            let mut next = snapshots.last().unwrap().clone();
+            for x in &mut next[start..=end] {
+                *x = cmp::min(*x, val);
+            }
+            snapshots.push(next);
+
+            assert_eq!(
+                tree.range_update(start..=end, Chmin(val)),
+                Ok(snapshots.len() - 1)
+            );
+        }
+
+        assert_eq!(tree.version_count(), snapshots.len());
+        for _ in 0..1000 {
+            let version = rng.gen_range(0..snapshots.len());
+            let a = rng.gen_range(0..100);
+            let b = rng.gen_range(0..100);
+            let (start, end) = (cmp::min(a, b), cmp::max(a, b));
+
+            let result = tree.query_at(version, start..=end).unwrap();
+            let expected = &snapshots[version][start..=end];
+            assert_eq!(result.min, *expected.iter().min().unwrap());
+            assert_eq!(result.max, *expected.iter().max().unwrap());
+        }
+    }
+
+    /**
+     * Sets every element to the value, which doesn't commute with itself.
+     */
+    #[derive(Copy, Clone, Debug)]
+    struct Assign(i64);
+
+    impl Tag<Sum> for Assign {
+        fn apply(&self, data: Sum) -> Sum {
+            if data.len == 0 {
+                return data;
+            }
+
+            Sum {
+                sum: self.0 as i128 * data.len as i128,
+                min: self.0,
+                max: self.0,
+                len: data.len,
+            }
+        }
+
+        fn compose(&self, _older: Self) -> Self {
+            *self
+        }
+    }
+
+    #[test]
+    fn test_non_commuting_tags() {
+        let leaves: Vec<Sum> = [1, 2, 3, 4].iter().map(|&x| Sum::new(x)).collect();
+        let mut tree: PersistentSegmentTree<Sum, Assign> = PersistentSegmentTree::new(&leaves);
+
+        tree.range_update(.., Assign(5)).unwrap();
+        tree.range_update(0..2, Assign(7)).unwrap();
+        tree.range_update(1..=1, Assign(0)).unwrap();
+
+        assert_eq!(tree.query_at(1, ..).unwrap().sum, 20);
+        assert_eq!(tree.query_at(2, ..).unwrap().sum, 24);
+        assert_eq!(tree.query_at(2, 1..3).unwrap().sum, 12);
+        let leaves: Vec<i128> = tree
+            .leaves_at(3)
+            .unwrap()
+            .iter()
+            .map(|leaf| leaf.sum)
+            .collect();
+        assert_eq!(leaves, vec![7, 0, 5, 5]);
+        let leaves: Vec<i128> = tree
+            .leaves_at(0)
+            .unwrap()
+            .iter()
+            .map(|leaf| leaf.sum)
+            .collect();
+        assert_eq!(leaves, vec![1, 2, 3, 4]);
+
+        assert_eq!(
+            tree.query_at(4, ..).map(|data| data.sum),
+            Err(RangeError::OutOfBounds { index: 4, len: 4 })
+        );
+        assert_eq!(
+            tree.range_update(2..=4, Assign(1)).map(|_| ()),
+            Err(RangeError::OutOfBounds { index: 4, len: 4 })
+        );
+        assert_eq!(tree.version_count(), 4);
+    }
+
+    #[test]
+    fn test_sum_versions() {
+        let leaves: Vec<Sum> = [1, 2, 3, 4, 5].iter().map(|&x| Sum::new(x)).collect();
+        let mut tree: PersistentSegmentTree<Sum, Add> = PersistentSegmentTree::new(&leaves);
+
+        tree.range_update(1..4, Add(10)).unwrap();
+        tree.range_update(.., Add(-1)).unwrap();
+
+        assert_eq!(tree.query_at(0, ..).unwrap().sum, 15);
+        assert_eq!(tree.query_at(1, 2..=3).unwrap().sum, 27);
+        assert_eq!(tree.range_query(2..=3).unwrap().sum, 25);
+        let leaves: Vec<i128> = tree
+            .leaves_at(2)
+            .unwrap()
+            .iter()
+            .map(|leaf| leaf.sum)
+            .collect();
+        assert_eq!(leaves, vec![0, 11, 12, 13, 4]);
+
+        // Elements that overflow wrap around, in the versions holding them only
+        let leaves: Vec<Sum> = [i64::MAX, 0, i64::MAX]
+            .iter()
+            .map(|&x| Sum::new(x))
+            .collect();
+        let mut tree: PersistentSegmentTree<Sum, Add> = PersistentSegmentTree::new(&leaves);
+        tree.range_update(.., Add(1)).unwrap();
+        tree.range_update(1.., Add(-1)).unwrap();
+
+        let leaves: Vec<i64> = tree
+            .leaves_at(2)
+            .unwrap()
+            .iter()
+            .map(|leaf| leaf.min)
+            .collect();
+        assert_eq!(leaves, vec![i64::MIN, 0, i64::MAX]);
+        assert_eq!(tree.query_at(1, 1..).unwrap().sum, i64::MIN as i128 + 1);
+        assert_eq!(tree.query_at(0, 1..).unwrap().sum, i64::MAX as i128);
+    }
+}
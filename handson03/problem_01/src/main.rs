@@ -1,39 +1,30 @@
+use std::error::Error;
 use std::io;
+use std::process;
 
-use crate::lib::plan_holiday;
-mod lib;
+use problem_01::plan_holiday;
+use token_reader::TokenReader;
 
 fn main() {
-    let mut buffer = String::new();
-    let stdin = io::stdin();
-
-    stdin.read_line(&mut buffer).expect("Failed to read line.");
-    let tmp: Vec<u32> = buffer
-        .split_whitespace()
-        .map(|x| x.parse().expect("Not an integer!"))
-        .collect();
-
-    buffer.clear();
-
-    if tmp.len() < 2 {
-        panic!("Missing input parameters.");
+    if let Err(why) = run() {
+        eprintln!("Error: {}", why);
+        process::exit(1);
     }
+}
 
-    let n: usize = *tmp.get(0).expect("Missing input parameters.") as usize;
-    let days: usize = *tmp.get(1).expect("Missing input parameters.") as usize;
-
-    let mut cities: Vec<Vec<u32>> = Vec::with_capacity(n * days);
-    for _i in 0..n {
-        stdin.read_line(&mut buffer).expect("Failed to read line.");
-        let city: Vec<u32> = buffer
-            .split_whitespace()
-            .map(|x| x.parse().expect("Not an integer!"))
-            .collect();
+fn run() -> Result<(), Box<dyn Error>> {
+    let mut reader = TokenReader::from_reader(io::stdin().lock())?;
 
-        cities.push(city);
+    let n: usize = reader.next("number of cities n")?;
+    let days: usize = reader.next("number of days D")?;
 
-        buffer.clear();
+    let mut cities: Vec<Vec<u32>> = Vec::new();
+    for _i in 0..n {
+        cities.push(reader.next_vec(days, "number of attractions")?);
     }
+    reader.finish()?;
 
     println!("{}", plan_holiday(n, days, cities));
+
+    Ok(())
 }
@@ -1,24 +1,31 @@
-mod lib;
+use std::error::Error;
 use std::io;
+use std::process;
 
-use crate::lib::count_reverse_flags;
+use problem_02::count_reverse_flags;
+use token_reader::TokenReader;
 
 fn main() {
-    let mut buffer = String::new();
-    let stdin = io::stdin();
+    if let Err(why) = run() {
+        eprintln!("Error: {}", why);
+        process::exit(1);
+    }
+}
 
-    stdin.read_line(&mut buffer).expect("Failed to read line.");
-    let _n: i32 = buffer.trim().parse().expect("Not an integer!");
+fn run() -> Result<(), Box<dyn Error>> {
+    let mut reader = TokenReader::from_reader(io::stdin().lock())?;
 
-    let mut houses = String::new();
-    stdin.read_line(&mut houses).expect("Failed to read line.");
+    let n: usize = reader.next("number of houses n")?;
 
-    match count_reverse_flags(&houses) {
-        Err(why) => {
-            panic!("Failed to count flags: {}", why);
-        }
-        Ok(flags) => {
-            println!("{}", flags);
-        }
+    let token = reader.expect_token("houses")?;
+    if token.text.chars().count() != n {
+        return Err(token.error(format!("a string of {} houses", n)).into());
     }
+    let flags = count_reverse_flags(token.text)
+        .map_err(|_| token.error("a string of R, W, G and X houses"))?;
+    reader.finish()?;
+
+    println!("{}", flags);
+
+    Ok(())
 }
@@ -0,0 +1,259 @@
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Read};
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+/**
+ * Whitespace separated token, along with the (1-based) line and
+ *  column of its first character.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub text: &'a str,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Token<'_> {
+    /**
+     * Returns an error at the token's position, saying that expected was expected instead.
+     */
+    pub fn error(&self, expected: impl Into<String>) -> ParseError {
+        ParseError {
+            line: self.line,
+            column: self.column,
+            expected: expected.into(),
+            found: Some(self.text.to_string()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub expected: String,
+    pub found: Option<String>, // None if the input ended
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.found {
+            Some(found) => write!(
+                f,
+                "{}:{}: expected {}, found '{}'",
+                self.line, self.column, self.expected, found
+            ),
+            None => write!(
+                f,
+                "{}:{}: expected {}, found end of input",
+                self.line, self.column, self.expected
+            ),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+/**
+ * Splits the input into tokens separated by any mix of spaces and
+ *  newlines, keeping track of their positions for error messages.
+ */
+#[derive(Debug)]
+pub struct TokenReader {
+    input: String,
+    pos: usize,    // Byte offset of the next character to read
+    line: usize,   // Line of the next character to read
+    column: usize, // Column of the next character to read
+}
+
+impl TokenReader {
+    pub fn new(input: String) -> Self {
+        Self {
+            input,
+            pos: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+
+    /**
+     * Reads the whole of reader and returns a TokenReader over it.
+     */
+    pub fn from_reader(mut reader: impl Read) -> io::Result<Self> {
+        let mut input = String::new();
+        reader.read_to_string(&mut input)?;
+
+        Ok(TokenReader::new(input))
+    }
+
+    /**
+     * Returns the next token, or None if the input is over.
+     */
+    pub fn next_token(&mut self) -> Option<Token<'_>> {
+        self.skip_whitespace();
+        if self.pos == self.input.len() {
+            return None;
+        }
+
+        let (start, line, column) = (self.pos, self.line, self.column);
+        let len = self.input[start..]
+            .find(char::is_whitespace)
+            .unwrap_or(self.input.len() - start);
+        self.pos += len;
+        self.column += self.input[start..self.pos].chars().count();
+
+        Some(Token {
+            text: &self.input[start..self.pos],
+            line,
+            column,
+        })
+    }
+
+    /**
+     * Returns the next token, or an error saying that what was
+     *  expected if the input is over.
+     */
+    pub fn expect_token(&mut self, what: &str) -> Result<Token<'_>, ParseError> {
+        // The position is only needed if there's no token, but is lost by then
+        self.skip_whitespace();
+        let (line, column) = (self.line, self.column);
+
+        self.next_token().ok_or_else(|| ParseError {
+            line,
+            column,
+            expected: what.to_string(),
+            found: None,
+        })
+    }
+
+    /**
+     * Parses the next token as a T, what describing it in error messages.
+     */
+    pub fn next<T: FromStr>(&mut self, what: &str) -> Result<T, ParseError> {
+        let token = self.expect_token(what)?;
+
+        token.text.parse().map_err(|_| token.error(what))
+    }
+
+    /**
+     * Parses the next token as a T in range, what describing it in error messages.
+     */
+    pub fn next_in<T: FromStr + PartialOrd + fmt::Display>(
+        &mut self,
+        range: RangeInclusive<T>,
+        what: &str,
+    ) -> Result<T, ParseError> {
+        let token = self.expect_token(what)?;
+
+        match token.text.parse() {
+            Ok(val) if range.contains(&val) => Ok(val),
+            _ => Err(token.error(format!("{} in [{}, {}]", what, range.start(), range.end()))),
+        }
+    }
+
+    /**
+     * Parses the next count tokens as Ts, what describing them in error messages.
+     *  Nothing is preallocated, since count comes from the input as well.
+     */
+    pub fn next_vec<T: FromStr>(&mut self, count: usize, what: &str) -> Result<Vec<T>, ParseError> {
+        (0..count).map(|_| self.next(what)).collect()
+    }
+
+    /**
+     * Returns an error if there are tokens left, as that means that
+     *  the input holds more items than it declared.
+     */
+    pub fn finish(&mut self) -> Result<(), ParseError> {
+        match self.next_token() {
+            Some(token) => Err(token.error("end of input")),
+            None => Ok(()),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        for c in self.input[self.pos..].chars() {
+            if !c.is_whitespace() {
+                break;
+            }
+
+            self.pos += c.len_utf8();
+            if c == '\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokens() {
+        let mut reader = TokenReader::new(String::from("3 2\n  1\t-2\n\n3\r\n x"));
+
+        assert_eq!(reader.next::<usize>("n"), Ok(3));
+        assert_eq!(reader.next_in(1..=5, "m"), Ok(2));
+        // Tokens can be split across lines in any way
+        assert_eq!(reader.next_vec::<i32>(3, "element"), Ok(vec![1, -2, 3]));
+        assert_eq!(
+            reader.next_token(),
+            Some(Token {
+                text: "x",
+                line: 5,
+                column: 2
+            })
+        );
+        assert_eq!(reader.next_token(), None);
+        assert_eq!(reader.finish(), Ok(()));
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut reader = TokenReader::new(String::from("1 foo\n 7 8"));
+
+        assert_eq!(reader.next::<u32>("n"), Ok(1));
+        assert_eq!(
+            reader.next::<u32>("m").unwrap_err().to_string(),
+            "1:3: expected m, found 'foo'"
+        );
+        assert_eq!(
+            reader.next_in(1..=5, "index").unwrap_err().to_string(),
+            "2:2: expected index in [1, 5], found '7'"
+        );
+        assert_eq!(
+            reader.finish().unwrap_err().to_string(),
+            "2:4: expected end of input, found '8'"
+        );
+        assert_eq!(
+            reader.next::<i32>("value").unwrap_err().to_string(),
+            "2:5: expected value, found end of input"
+        );
+    }
+
+    #[test]
+    fn test_declared_counts() {
+        // A count larger than the input fails instead of allocating it
+        let mut reader = TokenReader::new(String::from("1 2"));
+        assert_eq!(
+            reader
+                .next_vec::<i32>(usize::MAX, "element")
+                .unwrap_err()
+                .to_string(),
+            "1:4: expected element, found end of input"
+        );
+
+        // A count smaller than the input leaves tokens for finish to report
+        let mut reader = TokenReader::new(String::from("1 2 3"));
+        assert_eq!(reader.next_vec::<i32>(2, "element"), Ok(vec![1, 2]));
+        assert_eq!(
+            reader.finish().unwrap_err().to_string(),
+            "1:5: expected end of input, found '3'"
+        );
+    }
+}